@@ -0,0 +1,142 @@
+//! 比较中位数划分与SAH构建的BVH，以及串行与并行构建的耗时
+//! 场景为少量大三角形组成的起伏地面加上几团密集的小球，物体的大小与分布都很不均匀
+//! 运行：cargo run --release --example bvh_bench
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use nalgebra::Vector3;
+use rand::Rng;
+use rayon::prelude::*;
+
+use restart_raytrace::bvh::SplitMethod;
+use restart_raytrace::material::{Lambertian, Material};
+use restart_raytrace::ray::Ray;
+use restart_raytrace::sphere::Sphere;
+use restart_raytrace::texture::SolidColor;
+use restart_raytrace::triangle::Triangle;
+use restart_raytrace::{Hitable, RenderRng, BVH};
+
+/// 地面网格每边的格数，每格两个三角形
+const GRID: usize = 16;
+const CLUSTERS: usize = 8;
+const SPHERES_PER_CLUSTER: usize = 10_000;
+/// 每种划分方法追踪的光线数
+const RAYS: usize = 1_000_000;
+
+fn height(x: f64, z: f64) -> f64 {
+    0.5 * (x * 0.7).sin() * (z * 0.5).cos()
+}
+
+fn primitives(rng: &mut RenderRng) -> Vec<Box<dyn Hitable>> {
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::new(Vector3::new(
+        0.5, 0.5, 0.5,
+    ))));
+    let mut list: Vec<Box<dyn Hitable>> = Vec::new();
+
+    let size = 40.0;
+    let point = |i: usize, j: usize| {
+        let x = -size / 2.0 + size * i as f64 / GRID as f64;
+        let z = -size / 2.0 + size * j as f64 / GRID as f64;
+        Vector3::new(x, height(x, z), z)
+    };
+    for i in 0..GRID {
+        for j in 0..GRID {
+            let (p00, p10, p01, p11) = (
+                point(i, j),
+                point(i + 1, j),
+                point(i, j + 1),
+                point(i + 1, j + 1),
+            );
+            list.push(Box::new(Triangle::new(p00, p01, p10, material.clone())));
+            list.push(Box::new(Triangle::new(p10, p01, p11, material.clone())));
+        }
+    }
+
+    for _ in 0..CLUSTERS {
+        let center = Vector3::new(
+            rng.gen_range(-15.0..15.0),
+            rng.gen_range(1.0..4.0),
+            rng.gen_range(-15.0..15.0),
+        );
+        for _ in 0..SPHERES_PER_CLUSTER {
+            let offset = Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            list.push(Box::new(Sphere::new(
+                center + offset,
+                rng.gen_range(0.01..0.05),
+                material.clone(),
+            )));
+        }
+    }
+    list
+}
+
+/// 从场景上方的随机点射向地面随机点的光线
+fn rays(rng: &mut RenderRng) -> Vec<Ray> {
+    (0..RAYS)
+        .map(|_| {
+            let origin = Vector3::new(
+                rng.gen_range(-25.0..25.0),
+                rng.gen_range(5.0..15.0),
+                rng.gen_range(-25.0..25.0),
+            );
+            let target = Vector3::new(rng.gen_range(-20.0..20.0), 0.0, rng.gen_range(-20.0..20.0));
+            Ray::new(origin, target - origin, 0.0, 0.5)
+        })
+        .collect()
+}
+
+fn main() {
+    let rays = rays(&mut RenderRng::from_seed(1));
+    println!(
+        "{} triangles, {} spheres, {} rays",
+        2 * GRID * GRID,
+        CLUSTERS * SPHERES_PER_CLUSTER,
+        RAYS
+    );
+
+    let mut baseline = None;
+    for split_method in [SplitMethod::Median, SplitMethod::Sah] {
+        // 每次构建使用相同的物体
+        let list = primitives(&mut RenderRng::from_seed(0));
+        let serial = BVH::with_options(list, 0.0, 1.0, split_method, false).unwrap();
+        let list = primitives(&mut RenderRng::from_seed(0));
+        let bvh = BVH::with_options(list, 0.0, 1.0, split_method, true).unwrap();
+        assert!(
+            bvh.same_layout(&serial),
+            "parallel build differs from serial build"
+        );
+        let serial_build = serial.build_time();
+        drop(serial);
+
+        let start = Instant::now();
+        let hits = rays
+            .par_iter()
+            .filter(|r| bvh.hit(r, 0.001, f64::MAX).is_some())
+            .count();
+        let trace = start.elapsed();
+        let rays_per_second = RAYS as f64 / trace.as_secs_f64();
+        let speedup = baseline.map_or(1.0, |b| rays_per_second / b);
+        baseline.get_or_insert(rays_per_second);
+
+        println!(
+            "{:>6}: build {:7.1} ms serial, {:7.1} ms parallel, {} nodes",
+            split_method.to_string(),
+            serial_build.as_secs_f64() * 1e3,
+            bvh.build_time().as_secs_f64() * 1e3,
+            bvh.node_count()
+        );
+        println!(
+            "{:>6}  trace {:7.1} ms, {:6.2} Mrays/s, {:.2}x, {} hits",
+            "",
+            trace.as_secs_f64() * 1e3,
+            rays_per_second / 1e6,
+            speedup,
+            hits
+        );
+    }
+}
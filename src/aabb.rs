@@ -2,12 +2,13 @@ use nalgebra::Vector3;
 
 use super::ray::Ray;
 
-// AABB.0是左下角的坐标点
-// AABB.1是右上角的坐标点
+/// 轴对齐包围盒
+/// AABB.0是左下角的坐标点，AABB.1是右上角的坐标点
 #[derive(Clone, Copy)]
 pub struct AABB(Vector3<f64>, Vector3<f64>);
 
 impl AABB {
+    /// min：各坐标最小的角，max：各坐标最大的角
     pub fn new(min: Vector3<f64>, max: Vector3<f64>) -> AABB {
         AABB(min, max)
     }
@@ -17,6 +18,14 @@ impl AABB {
     pub fn max(&self) -> Vector3<f64> {
         self.1
     }
+    /// 表面积，SAH用它估计光线命中的概率
+    pub fn surface_area(&self) -> f64 {
+        let d = self.1 - self.0;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+    pub fn centroid(&self) -> Vector3<f64> {
+        0.5 * (self.0 + self.1)
+    }
     /// 返回是否和box相交
     pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         for i in 0..3 {
@@ -37,6 +46,7 @@ impl AABB {
     }
 }
 
+/// 同时包含两个盒子的最小包围盒
 pub fn surrounding_box(box0: &AABB, box1: &AABB) -> AABB {
     let min = Vector3::new(
         f64::min(box0.min()[0], box1.min()[0]),
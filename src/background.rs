@@ -0,0 +1,78 @@
+use nalgebra::Vector3;
+
+use crate::sphere::Sphere;
+use crate::texture::Texture;
+
+use super::ray::Ray;
+
+/// 光线未命中任何物体时返回的环境光
+pub trait Background: Send + Sync {
+    /// 沿光线r方向看到的颜色
+    fn value(&self, r: &Ray) -> Vector3<f64>;
+}
+
+/// 纯色背景，黑色用于只由光源照亮的场景
+pub struct SolidBackground {
+    color: Vector3<f64>,
+}
+
+impl SolidBackground {
+    /// 所有方向都返回color
+    pub fn new(color: Vector3<f64>) -> SolidBackground {
+        SolidBackground { color }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _r: &Ray) -> Vector3<f64> {
+        self.color
+    }
+}
+
+/// 渐变背景，按光线方向的y分量在bottom与top之间插值
+pub struct GradientBackground {
+    bottom: Vector3<f64>,
+    top: Vector3<f64>,
+}
+
+impl GradientBackground {
+    /// bottom：朝正下方的颜色，top：朝正上方的颜色
+    pub fn new(bottom: Vector3<f64>, top: Vector3<f64>) -> GradientBackground {
+        GradientBackground { bottom, top }
+    }
+
+    /// 默认的白-蓝天空
+    pub fn sky() -> GradientBackground {
+        GradientBackground::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for GradientBackground {
+    fn value(&self, r: &Ray) -> Vector3<f64> {
+        let unit_direction: Vector3<f64> = r.direction().normalize();
+        let t = 0.5 * (unit_direction[1] + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+/// 纹理背景，将光线方向按球面uv映射到纹理上
+pub struct TextureBackground {
+    texture: Box<dyn Texture>,
+}
+
+impl TextureBackground {
+    /// texture按经纬度映射到包围场景的球面上
+    pub fn new(texture: impl Texture + 'static) -> TextureBackground {
+        TextureBackground {
+            texture: Box::new(texture),
+        }
+    }
+}
+
+impl Background for TextureBackground {
+    fn value(&self, r: &Ray) -> Vector3<f64> {
+        let unit_direction: Vector3<f64> = r.direction().normalize();
+        let (u, v) = Sphere::get_sphere_uv(&unit_direction);
+        self.texture.value(u, v, unit_direction)
+    }
+}
@@ -1,129 +1,858 @@
-use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use nalgebra::Vector3;
+use rayon::prelude::*;
 
 use crate::{aabb::surrounding_box, hitable::HitRecord, ray::Ray};
 
 use super::{aabb::AABB, hitable::Hitable};
 
+/// 构建BVH时划分物体的方法
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitMethod {
+    /// 在分布最广的轴上按物体个数对半分，每个叶子一个物体
+    Median,
+    /// 分桶的表面积启发式（SAH），叶子可以包含多个物体
+    Sah,
+}
+
+impl fmt::Display for SplitMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SplitMethod::Median => "median",
+            SplitMethod::Sah => "sah",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for SplitMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "median" => Ok(SplitMethod::Median),
+            "sah" => Ok(SplitMethod::Sah),
+            _ => Err(format!(
+                "unknown BVH split method '{}' (expected median or sah)",
+                s
+            )),
+        }
+    }
+}
+
+/// 构建BVH的错误
+#[derive(Debug)]
+pub enum BVHError {
+    /// 第index个物体的包围盒含有NaN
+    InvalidBounds { index: usize },
+    /// 物体数超出了节点中下标的范围
+    TooManyPrimitives { count: usize },
+}
+
+impl fmt::Display for BVHError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BVHError::InvalidBounds { index } => {
+                write!(
+                    f,
+                    "object {} has a bounding box with NaN coordinates",
+                    index
+                )
+            }
+            BVHError::TooManyPrimitives { count } => write!(
+                f,
+                "too many objects for a BVH ({}, at most {})",
+                count,
+                u32::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BVHError {}
+
+/// SAH的代价模型，以一次物体求交的代价为1
+/// 遍历一个分叉节点（与包围盒求交）的相对代价
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+/// 每个轴上的桶数
+const SAH_BINS: usize = 16;
+/// 超过这个数目的物体一定继续划分
+const MAX_LEAF_SIZE: usize = 4;
+/// 遍历栈的容量，树的深度不能超过它
+const MAX_DEPTH: usize = 64;
+/// SAH超过这个深度后改为按个数对半分，保证剩余的深度不超过32
+const SAH_MAX_DEPTH: usize = MAX_DEPTH - 32;
+/// 物体少于这个数目时串行构建，避免任务调度的开销超过计算本身
+const PARALLEL_THRESHOLD: usize = 4096;
+
 /// 枚举：节点的内容
-/// Branch：分叉节点，左右子树信息
+/// Branch：分叉节点，左右子树信息与划分所在的轴
 /// Leaf：叶子节点，实际的渲染对象
 enum BVHNode {
-    Branch { left: Box<BVH>, right: Box<BVH> },
-    Leaf(Box<dyn Hitable>),
+    Branch {
+        left: Box<BuildNode>,
+        right: Box<BuildNode>,
+        axis: usize,
+    },
+    Leaf(Vec<Box<dyn Hitable>>),
 }
 
-///
-pub struct BVH {
+/// 构建时使用的指针树，构建完成后展开为LinearNode数组
+struct BuildNode {
     tree: BVHNode,
     bbox: AABB,
 }
 
-impl BVH {
-    /// hitlist是sphere的列表
-    pub fn new(mut hitlist: Vec<Box<dyn Hitable>>, time0: f64, time1: f64) -> Self {
-        // 递归二分处理所有hitlist中的对象
-
-        // 辅助函数1：创建一个闭包，用于在给定轴上比较两个bbox
-        // 比较该轴上的最大值与最小值之和
-        fn box_compare(
-            time0: f64,
-            time1: f64,
-            axis: usize,
-        ) -> impl FnMut(&Box<dyn Hitable>, &Box<dyn Hitable>) -> Ordering {
-            move |a, b| {
-                let a_bbox = a.bounding_box(time0, time1);
-                let b_bbox = b.bounding_box(time0, time1);
-                if let (Some(a), Some(b)) = (a_bbox, b_bbox) {
-                    let ac = a.min()[axis] + a.max()[axis];
-                    let bc = b.min()[axis] + b.max()[axis];
-                    ac.partial_cmp(&bc).unwrap()
-                } else {
-                    panic!["no bounding box in bvh node"]
-                }
+/// 展开后的节点，按深度优先顺序存放，第一个子节点紧跟在父节点之后
+/// 包围盒用f32保存并向外取整，保证仍然包住原来的包围盒
+#[repr(C)]
+#[derive(PartialEq)]
+struct LinearNode {
+    min: [f32; 3],
+    max: [f32; 3],
+    /// 叶子：第一个物体在primitives中的下标；分叉：第二个子节点的下标
+    offset: u32,
+    /// 叶子中物体的个数，0表示分叉节点
+    count: u16,
+    /// 分叉节点划分所在的轴
+    axis: u8,
+    _pad: u8,
+}
+
+const _: () = assert!(std::mem::size_of::<LinearNode>() == 32);
+
+/// 不小于x的最近的f32
+fn round_up(x: f64) -> f32 {
+    let y = x as f32;
+    if (y as f64) < x {
+        y.next_up()
+    } else {
+        y
+    }
+}
+
+/// 不大于x的最近的f32
+fn round_down(x: f64) -> f32 {
+    let y = x as f32;
+    if (y as f64) > x {
+        y.next_down()
+    } else {
+        y
+    }
+}
+
+impl LinearNode {
+    fn new(bbox: &AABB, offset: usize, count: usize, axis: usize) -> LinearNode {
+        LinearNode {
+            min: bbox.min().map(round_down).into(),
+            max: bbox.max().map(round_up).into(),
+            offset: offset as u32,
+            count: count as u16,
+            axis: axis as u8,
+            _pad: 0,
+        }
+    }
+
+    /// 与光线的slab测试，inv_direction为方向的倒数，direction_is_negative为方向各分量是否小于0
+    fn hit(
+        &self,
+        origin: &Vector3<f64>,
+        inv_direction: &Vector3<f64>,
+        direction_is_negative: [bool; 3],
+        mut t_min: f64,
+        mut t_max: f64,
+    ) -> bool {
+        for axis in 0..3 {
+            let (near, far) = if direction_is_negative[axis] {
+                (self.max[axis], self.min[axis])
+            } else {
+                (self.min[axis], self.max[axis])
+            };
+            let t0 = (near as f64 - origin[axis]) * inv_direction[axis];
+            let t1 = (far as f64 - origin[axis]) * inv_direction[axis];
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return false;
             }
         }
+        true
+    }
+}
+
+/// 构建时的物体，包围盒与中心只计算一次
+struct BuildItem {
+    hitable: Box<dyn Hitable>,
+    /// 在输入列表中的序号，用于打破排序时的平局
+    index: usize,
+    bbox: AABB,
+    centroid: Vector3<f64>,
+}
+
+/// SAH的一个桶：落在其中的物体个数与它们的包围盒
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    count: usize,
+    bbox: Option<AABB>,
+}
+
+fn union(a: Option<AABB>, b: Option<AABB>) -> Option<AABB> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(surrounding_box(&a, &b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
 
-        // 辅助函数2：给出hitlist在选定轴上的范围
-        fn axis_range(hitlist: &Vec<Box<dyn Hitable>>, time0: f64, time1: f64, axis: usize) -> f64 {
-            let (min, max) = hitlist
+/// 层次包围盒，加速光线与大量物体的求交
+/// 节点展开为连续的数组，叶子引用primitives中连续的一段，遍历用显式的栈代替递归
+/// 没有包围盒的物体（如无限大的平面）不进入树，每条光线都与它们逐个求交
+pub struct BVH {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<Box<dyn Hitable>>,
+    /// 树中所有物体的包围盒，树为空时为None
+    bbox: Option<AABB>,
+    unbounded: Vec<Box<dyn Hitable>>,
+    build_time: Duration,
+}
+
+/// 包围盒的坐标是否都是有限的数
+fn is_finite(bbox: &AABB) -> bool {
+    bbox.min()
+        .iter()
+        .chain(bbox.max().iter())
+        .all(|x| x.is_finite())
+}
+
+fn has_nan(bbox: &AABB) -> bool {
+    bbox.min()
+        .iter()
+        .chain(bbox.max().iter())
+        .any(|x| x.is_nan())
+}
+
+impl BVH {
+    /// 用SAH并行构建
+    /// hitlist：参与加速的物体，可以为空；包围盒为None或无穷大的物体放在树外
+    /// time0、time1：快门开闭的时刻，运动物体取这段时间内的包围盒
+    pub fn new(hitlist: Vec<Box<dyn Hitable>>, time0: f64, time1: f64) -> Result<Self, BVHError> {
+        BVH::with_split_method(hitlist, time0, time1, SplitMethod::Sah)
+    }
+
+    /// 用指定的划分方法并行构建，参数同new
+    pub fn with_split_method(
+        hitlist: Vec<Box<dyn Hitable>>,
+        time0: f64,
+        time1: f64,
+        split_method: SplitMethod,
+    ) -> Result<Self, BVHError> {
+        BVH::with_options(hitlist, time0, time1, split_method, true)
+    }
+
+    /// parallel：是否用rayon并行构建，并行与串行得到的树完全相同
+    pub fn with_options(
+        hitlist: Vec<Box<dyn Hitable>>,
+        time0: f64,
+        time1: f64,
+        split_method: SplitMethod,
+        parallel: bool,
+    ) -> Result<Self, BVHError> {
+        let start = Instant::now();
+        if hitlist.len() > u32::MAX as usize {
+            return Err(BVHError::TooManyPrimitives {
+                count: hitlist.len(),
+            });
+        }
+        let bboxes: Vec<Option<AABB>> = if parallel {
+            hitlist
+                .par_iter()
+                .map(|hitable| hitable.bounding_box(time0, time1))
+                .collect()
+        } else {
+            hitlist
                 .iter()
-                .fold((f64::MAX, f64::MIN), |(bmin, bmax), bhit| {
-                    if let Some(bbox) = bhit.bounding_box(time0, time1) {
-                        (bmin.min(bbox.min()[axis]), bmax.max(bbox.max()[axis]))
-                    } else {
-                        (bmin, bmax)
-                    }
-                });
-            max - min
+                .map(|hitable| hitable.bounding_box(time0, time1))
+                .collect()
+        };
+
+        let mut items = Vec::with_capacity(hitlist.len());
+        let mut unbounded = Vec::new();
+        for (index, (hitable, bbox)) in hitlist.into_iter().zip(bboxes).enumerate() {
+            match bbox {
+                Some(bbox) if has_nan(&bbox) => return Err(BVHError::InvalidBounds { index }),
+                Some(bbox) if is_finite(&bbox) => items.push(BuildItem {
+                    hitable,
+                    index,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }),
+                _ => unbounded.push(hitable),
+            }
+        }
+
+        let mut bvh = BVH {
+            nodes: Vec::new(),
+            primitives: Vec::new(),
+            bbox: None,
+            unbounded,
+            build_time: Duration::ZERO,
+        };
+        if !items.is_empty() {
+            let builder = Builder { parallel };
+            let root = match split_method {
+                SplitMethod::Median => builder.build_median(items),
+                SplitMethod::Sah => builder.build_sah(items, 0),
+            };
+            bvh.bbox = Some(root.bbox);
+            bvh.flatten(root);
+        }
+        bvh.build_time = start.elapsed();
+        Ok(bvh)
+    }
+
+    /// 构建所用的时间
+    pub fn build_time(&self) -> Duration {
+        self.build_time
+    }
+
+    /// 展开后的节点数，包括分叉与叶子
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 放在树外、每次都要求交的物体数
+    pub fn unbounded_count(&self) -> usize {
+        self.unbounded.len()
+    }
+
+    /// 两棵树的节点数组是否完全相同，即包围盒、划分轴与每个叶子的物体区间都一样
+    pub fn same_layout(&self, other: &BVH) -> bool {
+        self.nodes == other.nodes
+    }
+
+    /// 深度优先展开以node为根的子树，返回node在数组中的下标
+    fn flatten(&mut self, node: BuildNode) -> usize {
+        let index = self.nodes.len();
+        match node.tree {
+            BVHNode::Leaf(hitables) => {
+                self.nodes.push(LinearNode::new(
+                    &node.bbox,
+                    self.primitives.len(),
+                    hitables.len(),
+                    0,
+                ));
+                self.primitives.extend(hitables);
+            }
+            BVHNode::Branch { left, right, axis } => {
+                self.nodes.push(LinearNode::new(&node.bbox, 0, 0, axis));
+                self.flatten(*left);
+                let second = self.flatten(*right);
+                self.nodes[index].offset = second as u32;
+            }
+        }
+        index
+    }
+}
+
+/// 递归构建BuildNode树
+/// 并行时，物体数不少于PARALLEL_THRESHOLD的节点内的遍历、排序与划分用rayon完成，
+/// 左右子树用rayon::join同时构建；这些操作的结果与串行时逐位相同
+struct Builder {
+    parallel: bool,
+}
+
+impl Builder {
+    fn is_parallel(&self, len: usize) -> bool {
+        self.parallel && len >= PARALLEL_THRESHOLD
+    }
+
+    fn leaf(items: Vec<BuildItem>, bbox: AABB) -> BuildNode {
+        BuildNode {
+            tree: BVHNode::Leaf(items.into_iter().map(|item| item.hitable).collect()),
+            bbox,
+        }
+    }
+
+    /// 所有物体的包围盒与中心的范围
+    fn bounds(&self, items: &[BuildItem]) -> (AABB, AABB) {
+        let bounds = |item: &BuildItem| (item.bbox, AABB::new(item.centroid, item.centroid));
+        let merge = |(a, ca): (AABB, AABB), (b, cb): (AABB, AABB)| {
+            (surrounding_box(&a, &b), surrounding_box(&ca, &cb))
+        };
+        if self.is_parallel(items.len()) {
+            items.par_iter().map(bounds).reduce_with(merge).unwrap()
+        } else {
+            items.iter().map(bounds).reduce(merge).unwrap()
+        }
+    }
+
+    /// 按中心在axis上的坐标排序，坐标相同时按原来的序号，使排序结果唯一
+    fn sort(&self, items: &mut [BuildItem], axis: usize) {
+        let compare = |a: &BuildItem, b: &BuildItem| {
+            a.centroid[axis]
+                .total_cmp(&b.centroid[axis])
+                .then(a.index.cmp(&b.index))
+        };
+        if self.is_parallel(items.len()) {
+            items.par_sort_unstable_by(compare);
+        } else {
+            items.sort_unstable_by(compare);
         }
+    }
+
+    /// 左右子树，物体足够多时并行构建
+    fn build_children(
+        &self,
+        left: Vec<BuildItem>,
+        right: Vec<BuildItem>,
+        build: impl Fn(Vec<BuildItem>) -> BuildNode + Sync,
+    ) -> (BuildNode, BuildNode) {
+        if self.is_parallel(left.len() + right.len()) {
+            rayon::join(|| build(left), || build(right))
+        } else {
+            (build(left), build(right))
+        }
+    }
+
+    fn branch(left: BuildNode, right: BuildNode, axis: usize, bbox: AABB) -> BuildNode {
+        BuildNode {
+            tree: BVHNode::Branch {
+                left: Box::new(left),
+                right: Box::new(right),
+                axis,
+            },
+            bbox,
+        }
+    }
 
-        // 找到分布最广的轴
-        let (axis, _) = (0..3)
-            .map(|a| (a, axis_range(&hitlist, time0, time1, a)))
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+    /// 在包围盒最长的轴上按中心排序，从中间分开
+    fn build_median(&self, mut items: Vec<BuildItem>) -> BuildNode {
+        let (bbox, _) = self.bounds(&items);
+        let len = items.len();
+        if len == 1 {
+            return Builder::leaf(items, bbox);
+        }
+        let extent = bbox.max() - bbox.min();
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
             .unwrap();
-        // 在该轴上给hitlist排序
-        hitlist.sort_unstable_by(box_compare(time0, time1, axis));
-
-        // 处理最后得到的hitlist，构建一个BVH树，叶子节点是object的包围盒->object
-        let len = hitlist.len();
-        match len {
-            0 => panic!("no elements in the world"),
-            1 => {
-                let leaf = hitlist.pop().unwrap();
-                if let Some(bbox) = leaf.bounding_box(time0, time1) {
-                    return BVH {
-                        tree: BVHNode::Leaf(leaf),
-                        bbox,
-                    };
+        self.sort(&mut items, axis);
+        let right = items.split_off(len / 2);
+        let (left, right) = self.build_children(items, right, |items| self.build_median(items));
+        Builder::branch(left, right, axis, bbox)
+    }
+
+    /// depth：当前节点的深度，根为0
+    fn build_sah(&self, mut items: Vec<BuildItem>, depth: usize) -> BuildNode {
+        let (bbox, centroid_bounds) = self.bounds(&items);
+        let len = items.len();
+        if len == 1 {
+            return Builder::leaf(items, bbox);
+        }
+
+        let centroid_min = centroid_bounds.min();
+        let extent = centroid_bounds.max() - centroid_min;
+        // 所有物体共面或共线时包围盒面积为0，SAH代价没有意义，与中心重合时同样按个数对半分
+        let area = bbox.surface_area();
+        let use_sah = area > 0.0 && area.is_finite() && depth < SAH_MAX_DEPTH;
+        // 在三个轴上分别找代价最小的桶边界
+        let bin_of = |item: &BuildItem, axis: usize| {
+            let offset = (item.centroid[axis] - centroid_min[axis]) / extent[axis];
+            ((offset * SAH_BINS as f64) as usize).min(SAH_BINS - 1)
+        };
+        let best = (0..3)
+            .filter(|&axis| use_sah && extent[axis] > 0.0)
+            .map(|axis| {
+                let bins = self.bins(&items, |item| bin_of(item, axis));
+                let (split, cost) = sah_split(&bins, &bbox);
+                (axis, split, cost)
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        let (axis, right) = match best {
+            Some((axis, split, split_cost)) => {
+                let leaf_cost = INTERSECTION_COST * len as f64;
+                if len <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+                    return Builder::leaf(items, bbox);
+                }
+                // 最小与最大的中心分别落在第一个与最后一个桶，两侧都不会为空
+                let is_left = |item: &BuildItem| bin_of(item, axis) <= split;
+                let (left, right): (Vec<_>, Vec<_>) = if self.is_parallel(len) {
+                    items.into_par_iter().partition(is_left)
                 } else {
-                    panic!("no bounding box in bvh node")
+                    items.into_iter().partition(is_left)
                 };
+                items = left;
+                (axis, right)
             }
-            _ => {
-                let right = BVH::new(hitlist.drain(len / 2..).collect(), time0, time1);
-                let left = BVH::new(hitlist, time0, time1);
-                let bbox = surrounding_box(&left.bbox, &right.bbox);
-                return BVH {
-                    tree: BVHNode::Branch {
-                        left: Box::new(left),
-                        right: Box::new(right),
-                    },
-                    bbox: bbox,
-                };
+            None => {
+                // 所有中心重合、包围盒退化或树已经太深：在中心分布最广的轴上按个数对半分
+                if len <= MAX_LEAF_SIZE {
+                    return Builder::leaf(items, bbox);
+                }
+                let axis = extent.imax();
+                self.sort(&mut items, axis);
+                (axis, items.split_off(len / 2))
             }
+        };
+
+        let (left, right) =
+            self.build_children(items, right, |items| self.build_sah(items, depth + 1));
+        Builder::branch(left, right, axis, bbox)
+    }
+
+    /// 把物体按bin_of分到SAH_BINS个桶中
+    fn bins(
+        &self,
+        items: &[BuildItem],
+        bin_of: impl Fn(&BuildItem) -> usize + Sync,
+    ) -> [Bin; SAH_BINS] {
+        let count = |items: &[BuildItem]| {
+            let mut bins = [Bin::default(); SAH_BINS];
+            for item in items {
+                let bin = &mut bins[bin_of(item)];
+                bin.count += 1;
+                bin.bbox = union(bin.bbox, Some(item.bbox));
+            }
+            bins
+        };
+        if self.is_parallel(items.len()) {
+            // 各段分别计数后合并，计数与并集的结果与合并顺序无关
+            items
+                .par_chunks(PARALLEL_THRESHOLD)
+                .map(count)
+                .reduce_with(|mut a, b| {
+                    for (a, b) in a.iter_mut().zip(b) {
+                        a.count += b.count;
+                        a.bbox = union(a.bbox, b.bbox);
+                    }
+                    a
+                })
+                .unwrap()
+        } else {
+            count(items)
         }
     }
 }
 
+/// 在桶的边界中找代价最小的划分，返回划分与它的代价
+/// 划分i表示第0到第i个桶在左侧，其余在右侧；bbox为节点的包围盒，面积必须大于0
+fn sah_split(bins: &[Bin; SAH_BINS], bbox: &AABB) -> (usize, f64) {
+    // 从右往左累积，right_costs[i]为第i+1个桶到最后一个桶的面积×个数
+    let mut right_costs = [0.0; SAH_BINS - 1];
+    let mut accumulated = Bin::default();
+    for i in (1..SAH_BINS).rev() {
+        accumulated.count += bins[i].count;
+        accumulated.bbox = union(accumulated.bbox, bins[i].bbox);
+        right_costs[i - 1] =
+            accumulated.count as f64 * accumulated.bbox.map_or(0.0, |b| b.surface_area());
+    }
+    let mut accumulated = Bin::default();
+    (0..SAH_BINS - 1)
+        .map(|i| {
+            accumulated.count += bins[i].count;
+            accumulated.bbox = union(accumulated.bbox, bins[i].bbox);
+            let left_cost =
+                accumulated.count as f64 * accumulated.bbox.map_or(0.0, |b| b.surface_area());
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST * (left_cost + right_costs[i]) / bbox.surface_area();
+            (i, cost)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+}
+
 impl Hitable for BVH {
     fn hit(&self, r: &Ray, t_min: f64, mut t_max: f64) -> Option<HitRecord> {
-        // 递归，直到命中最近的子节点
-        if self.bbox.hit(r, t_min, t_max) {
-            // 与包围盒相交
-            match &self.tree {
-                BVHNode::Branch { left, right } => {
-                    // left是较近的，如果命中，更新t_max
-                    let left = left.hit(r, t_min, t_max);
-                    if let Some(l) = &left {
-                        t_max = l.time()
-                    };
-                    // 左右有重叠，更新t_max仍与右节点相交，右节点是最近的
-                    let right = right.hit(r, t_min, t_max);
-                    if right.is_some() {
-                        right
-                    } else {
-                        left
+        let origin = r.origin();
+        let inv_direction = r.direction().map(|d| 1.0 / d);
+        let direction_is_negative = [
+            inv_direction.x < 0.0,
+            inv_direction.y < 0.0,
+            inv_direction.z < 0.0,
+        ];
+
+        let mut closest = None;
+        for hitable in &self.unbounded {
+            if let Some(rec) = hitable.hit(r, t_min, t_max) {
+                t_max = rec.time();
+                closest = Some(rec);
+            }
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            if node.hit(&origin, &inv_direction, direction_is_negative, t_min, t_max) {
+                if node.count > 0 {
+                    // 叶子：逐个求交，命中后缩短t_max
+                    let first = node.offset as usize;
+                    for primitive in &self.primitives[first..first + node.count as usize] {
+                        if let Some(rec) = primitive.hit(r, t_min, t_max) {
+                            t_max = rec.time();
+                            closest = Some(rec);
+                        }
                     }
+                } else {
+                    // 先访问光线方向上较近的子节点，另一个压栈
+                    let (near, far) = if direction_is_negative[node.axis as usize] {
+                        (node.offset as usize, index + 1)
+                    } else {
+                        (index + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    index = near;
+                    continue;
                 }
-                BVHNode::Leaf(leaf) => leaf.hit(r, t_min, t_max),
             }
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            index = stack[stack_len];
+        }
+        closest
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        if self.unbounded.is_empty() {
+            self.bbox
         } else {
             None
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use rand::Rng;
+
+    use crate::hitable::HitableList;
+    use crate::material::Lambertian;
+    use crate::rng::RenderRng;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+    use crate::triangle::Triangle;
+
+    fn gray() -> Lambertian {
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5)))
+    }
 
-    fn bounding_box(&self, _time0: f64, _time11: f64) -> Option<AABB> {
-        Some(self.bbox)
+    /// 半径为0的球排成一条线，所有包围盒的面积都是0
+    fn points() -> Vec<Box<dyn Hitable>> {
+        (0..100)
+            .map(|i| {
+                Box::new(Sphere::new(Vector3::new(i as f64, 0.0, 0.0), 0.0, gray()))
+                    as Box<dyn Hitable>
+            })
+            .collect()
+    }
+
+    /// 退化成线段的三角形，全部位于同一平面内
+    fn segments() -> Vec<Box<dyn Hitable>> {
+        (0..100)
+            .map(|i| {
+                let p = Vector3::new(0.0, i as f64, 0.0);
+                Box::new(Triangle::new(
+                    p,
+                    p + Vector3::x(),
+                    p + 2.0 * Vector3::x(),
+                    gray(),
+                )) as Box<dyn Hitable>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn degenerate_bounds_do_not_panic() {
+        for hitlist in [points as fn() -> _, segments] {
+            for split_method in [SplitMethod::Median, SplitMethod::Sah] {
+                let bvh = BVH::with_split_method(hitlist(), 0.0, 1.0, split_method).unwrap();
+                assert_eq!(bvh.primitives.len(), 100);
+                assert!(bvh
+                    .nodes
+                    .iter()
+                    .all(|node| node.count as usize <= MAX_LEAF_SIZE));
+            }
+        }
+    }
+
+    /// y = 0平面，没有包围盒
+    struct Plane;
+
+    impl Hitable for Plane {
+        fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+            let t = -r.origin().y / r.direction().y;
+            (t > t_min && t < t_max)
+                .then(|| HitRecord::new(r.at(t), Vector3::y(), t, Arc::new(gray()), 0.0, 0.0))
+        }
+        fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+            None
+        }
+    }
+
+    /// 包围盒含有NaN的物体
+    struct Broken;
+
+    impl Hitable for Broken {
+        fn hit(&self, _r: &Ray, _t_min: f64, _t_max: f64) -> Option<HitRecord> {
+            None
+        }
+        fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+            Some(AABB::new(Vector3::repeat(f64::NAN), Vector3::zeros()))
+        }
+    }
+
+    fn down(x: f64) -> Ray {
+        Ray::new(
+            Vector3::new(x, 5.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            0.0,
+            0.5,
+        )
+    }
+
+    #[test]
+    fn empty_bvh_never_hits() {
+        let bvh = BVH::new(Vec::new(), 0.0, 1.0).unwrap();
+        assert!(bvh.hit(&down(0.0), 0.001, f64::MAX).is_none());
+        assert!(bvh.bounding_box(0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn unbounded_objects_stay_outside_the_tree() {
+        let hitlist: Vec<Box<dyn Hitable>> = vec![
+            Box::new(Plane),
+            Box::new(Sphere::new(Vector3::new(0.0, 2.0, 0.0), 1.0, gray())),
+        ];
+        let bvh = BVH::new(hitlist, 0.0, 1.0).unwrap();
+        assert_eq!(bvh.unbounded_count(), 1);
+        assert!(bvh.bounding_box(0.0, 1.0).is_none());
+        // 球挡在平面前面
+        assert_eq!(bvh.hit(&down(0.0), 0.001, f64::MAX).unwrap().time(), 2.0);
+        assert_eq!(bvh.hit(&down(5.0), 0.001, f64::MAX).unwrap().time(), 5.0);
+    }
+
+    #[test]
+    fn nan_bounds_are_an_error() {
+        let hitlist: Vec<Box<dyn Hitable>> = vec![Box::new(Plane), Box::new(Broken)];
+        assert!(matches!(
+            BVH::new(hitlist, 0.0, 1.0),
+            Err(BVHError::InvalidBounds { index: 1 })
+        ));
+    }
+
+    fn random_point(rng: &mut RenderRng, size: f64) -> Vector3<f64> {
+        Vector3::new(
+            rng.gen_range(-size..size),
+            rng.gen_range(-size..size),
+            rng.gen_range(-size..size),
+        )
+    }
+
+    /// n个随机的球与三角形，大小相差很大，同一种子总是得到同样的物体
+    fn random_scene(seed: u64, n: usize) -> Vec<Box<dyn Hitable>> {
+        let mut rng = RenderRng::from_seed(seed);
+        (0..n)
+            .map(|i| {
+                let center = random_point(&mut rng, 10.0);
+                let size = if i % 7 == 0 { 2.0 } else { 0.1 };
+                if i % 2 == 0 {
+                    Box::new(Sphere::new(center, rng.gen_range(0.01..size), gray()))
+                        as Box<dyn Hitable>
+                } else {
+                    let a = center + random_point(&mut rng, size);
+                    let b = center + random_point(&mut rng, size);
+                    Box::new(Triangle::new(center, a, b, gray())) as Box<dyn Hitable>
+                }
+            })
+            .collect()
+    }
+
+    /// 比较BVH与逐个求交的列表对同一批光线的最近交点
+    fn assert_matches_brute_force(bvh: &BVH, seed: u64, n: usize, rays: usize) {
+        let mut list = HitableList::new();
+        for hitable in random_scene(seed, n) {
+            list.push(hitable);
+        }
+        let mut rng = RenderRng::from_seed(seed + 1);
+        for _ in 0..rays {
+            let origin = random_point(&mut rng, 15.0);
+            let ray = Ray::new(origin, random_point(&mut rng, 1.0), 0.0, 0.5);
+            let expected = list.hit(&ray, 0.001, f64::MAX).map(|rec| rec.time());
+            let actual = bvh.hit(&ray, 0.001, f64::MAX).map(|rec| rec.time());
+            assert_eq!(actual, expected, "n = {}, ray {:?}", n, ray.direction());
+        }
+    }
+
+    #[test]
+    fn parallel_build_matches_serial_build() {
+        // 足够多的物体，使根附近的节点走并行的分支
+        let n = 2 * PARALLEL_THRESHOLD;
+        for split_method in [SplitMethod::Median, SplitMethod::Sah] {
+            let serial =
+                BVH::with_options(random_scene(1, n), 0.0, 1.0, split_method, false).unwrap();
+            let parallel =
+                BVH::with_options(random_scene(1, n), 0.0, 1.0, split_method, true).unwrap();
+            assert!(
+                parallel.same_layout(&serial),
+                "{} split differs",
+                split_method
+            );
+            assert_matches_brute_force(&parallel, 1, n, 100);
+        }
+    }
+
+    #[test]
+    fn flattened_traversal_matches_brute_force() {
+        for (seed, n) in [0, 1, 2, 3, 5, 17, 100, 1000, 3000].into_iter().enumerate() {
+            for split_method in [SplitMethod::Median, SplitMethod::Sah] {
+                let bvh =
+                    BVH::with_options(random_scene(seed as u64, n), 0.0, 1.0, split_method, false)
+                        .unwrap();
+                assert_matches_brute_force(&bvh, seed as u64, n, 200);
+            }
+        }
+    }
+
+    #[test]
+    fn axis_aligned_rays_match_brute_force() {
+        // 方向分量为0时倒数为无穷大，slab测试要靠f32包围盒向外取整保证不漏掉交点
+        let n = 500;
+        let bvh = BVH::new(random_scene(7, n), 0.0, 1.0).unwrap();
+        let mut list = HitableList::new();
+        for hitable in random_scene(7, n) {
+            list.push(hitable);
+        }
+        let mut rng = RenderRng::from_seed(8);
+        for _ in 0..300 {
+            let origin = random_point(&mut rng, 12.0);
+            for direction in [
+                Vector3::x(),
+                -Vector3::x(),
+                Vector3::y(),
+                -Vector3::y(),
+                Vector3::z(),
+                -Vector3::z(),
+            ] {
+                let ray = Ray::new(origin, direction, 0.0, 0.5);
+                let expected = list.hit(&ray, 0.001, f64::MAX).map(|rec| rec.time());
+                let actual = bvh.hit(&ray, 0.001, f64::MAX).map(|rec| rec.time());
+                assert_eq!(actual, expected);
+            }
+        }
     }
 }
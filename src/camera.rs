@@ -1,8 +1,10 @@
 use nalgebra::Vector3;
-use rand::Rng;
+use std::f64::consts::FRAC_PI_4;
 
 use super::ray::Ray;
+use super::sampler::Sampler;
 
+/// 薄透镜相机，支持景深与运动模糊
 pub struct Camera {
     origin: Vector3<f64>,
     lower_left_corner: Vector3<f64>,
@@ -15,6 +17,9 @@ pub struct Camera {
     time1: f64,
 }
 impl Camera {
+    /// 从lookfrom看向lookat，vup决定画面的上方
+    /// 快门在time0打开、time1关闭，光线的时刻在其间均匀分布
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         lookfrom: Vector3<f64>,
         lookat: Vector3<f64>,
@@ -54,25 +59,28 @@ impl Camera {
         }
     }
 
-    /// 输入viewport中的相对坐标
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * random_in_unit_dsk();
+    /// 输入viewport中的相对坐标，以及该采样的采样器
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * random_in_unit_dsk(sampler);
         let origin = self.origin + self.u * rd[0] + self.v * rd[1];
         let direction = self.lower_left_corner + s * self.horizontal + t * self.vertical - origin;
-        Ray::new(
-            origin,
-            direction,
-            rand::thread_rng().gen_range(self.time0..self.time1),
-        )
+        let time = self.time0 + sampler.get_1d() * (self.time1 - self.time0);
+        Ray::new(origin, direction, time, sampler.get_1d())
     }
 }
 
-fn random_in_unit_dsk() -> Vector3<f64> {
-    let mut rng = rand::thread_rng();
-    loop {
-        let p = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
-        if p.norm() < 1.0 {
-            return p;
-        }
+/// 单位圆盘内均匀分布的点
+/// 用同心映射把正方形映射到圆盘，保持采样的分层结构
+fn random_in_unit_dsk(sampler: &mut dyn Sampler) -> Vector3<f64> {
+    let (u1, u2) = sampler.get_2d();
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vector3::new(0.0, 0.0, 0.0);
     }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (a / b))
+    };
+    Vector3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
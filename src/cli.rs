@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use restart_raytrace::exr::ExrCompression;
+use restart_raytrace::filter::FilterKind;
+use restart_raytrace::output::ImageFormat;
+use restart_raytrace::sampler::SamplerKind;
+use restart_raytrace::scene::ImageOverrides;
+use restart_raytrace::tile::TileOrder;
+use restart_raytrace::tonemap::{ToneMapOperator, ToneMapping};
+
+/// 命令行参数
+/// 图像参数未给出时使用场景文件中的值，内置场景使用ImageSettings的默认值
+#[derive(Parser, Debug)]
+#[command(version, about = "A small path tracer")]
+pub struct Args {
+    /// Built-in scene name (see --list-scenes) or path to a TOML scene file
+    #[arg(short, long, default_value = "two-spheres")]
+    pub scene: String,
+
+    /// Print the names of the built-in scenes and exit
+    #[arg(long)]
+    pub list_scenes: bool,
+
+    /// Image width in pixels
+    #[arg(short, long, value_parser = parse_positive_usize)]
+    pub width: Option<usize>,
+
+    /// Image width divided by image height
+    #[arg(short, long, value_parser = parse_positive_f64)]
+    pub aspect_ratio: Option<f64>,
+
+    /// Samples per pixel
+    #[arg(long = "spp", value_parser = parse_positive_usize)]
+    pub samples_per_pixel: Option<usize>,
+
+    /// Maximum number of bounces per path
+    #[arg(short = 'd', long, value_parser = parse_positive_usize)]
+    pub max_depth: Option<usize>,
+
+    /// Output file (.ppm, .png, .pfm or .exr), or '-' for stdout
+    #[arg(short, long, default_value = "output.png")]
+    pub output: PathBuf,
+
+    /// Output image format: ppm, png, pfm or exr [default: from the output extension, ppm for stdout]
+    #[arg(short, long)]
+    pub format: Option<ImageFormat>,
+
+    /// Pixel reconstruction filter: box, tent, gaussian, mitchell or lanczos
+    #[arg(long, default_value_t = FilterKind::Gaussian)]
+    pub filter: FilterKind,
+
+    /// Filter radius in pixels [default: 0.5 box, 1 tent, 1.5 gaussian, 2 mitchell, 3 lanczos]
+    #[arg(long, value_parser = parse_positive_f64)]
+    pub filter_radius: Option<f64>,
+
+    /// Exposure compensation in stops, applied before tone mapping (8-bit formats only)
+    #[arg(
+        short,
+        long,
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        value_parser = parse_finite_f64
+    )]
+    pub exposure: f64,
+
+    /// Tone mapping operator: clamp, reinhard, extended-reinhard, aces or hable
+    #[arg(short, long, default_value_t = ToneMapOperator::Clamp)]
+    pub tonemap: ToneMapOperator,
+
+    /// Luminance that maps to white for extended-reinhard [default: brightest pixel]
+    #[arg(long, value_parser = parse_positive_f64)]
+    pub white_point: Option<f64>,
+
+    /// OpenEXR compression: none or zip
+    #[arg(long, default_value_t = ExrCompression::Zip)]
+    pub exr_compression: ExrCompression,
+
+    /// Number of render threads, 0 uses every core
+    #[arg(short = 'j', long, default_value_t = 0)]
+    pub threads: usize,
+
+    /// Edge length of the square tiles the image is split into, in pixels
+    #[arg(long, default_value_t = 32, value_parser = parse_positive_usize)]
+    pub tile_size: usize,
+
+    /// Order in which tiles are rendered: scanline or spiral
+    #[arg(long, default_value_t = TileOrder::Spiral)]
+    pub tile_order: TileOrder,
+
+    /// Do not report progress on stderr
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Random seed; the same seed always renders the same image
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Sample generator: independent, stratified, halton or sobol
+    #[arg(long, default_value_t = SamplerKind::Sobol)]
+    pub sampler: SamplerKind,
+}
+
+impl Args {
+    pub fn filter_radius(&self) -> f64 {
+        self.filter_radius
+            .unwrap_or_else(|| self.filter.default_radius())
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        ToneMapping {
+            exposure: self.exposure,
+            operator: self.tonemap,
+            white_point: self.white_point,
+        }
+    }
+
+    pub fn image_overrides(&self) -> ImageOverrides {
+        ImageOverrides {
+            width: self.width,
+            aspect_ratio: self.aspect_ratio,
+            samples_per_pixel: self.samples_per_pixel,
+            max_depth: self.max_depth,
+        }
+    }
+}
+
+fn parse_positive_usize(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("expected a positive integer, found '{}'", s)),
+    }
+}
+
+fn parse_finite_f64(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(x),
+        _ => Err(format!("expected a finite number, found '{}'", s)),
+    }
+}
+
+fn parse_positive_f64(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+        _ => Err(format!("expected a positive number, found '{}'", s)),
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+
+use super::hitable::{FlipFace, HitRecord, Hitable, HitableList};
+use super::material::Material;
+use super::ray::Ray;
+use super::rect::{XYRect, XZRect, YZRect};
+use nalgebra::Vector3;
+
+/// 轴对齐长方体，由六个矩形组成，法线均朝外
+pub struct Cuboid {
+    box_min: Vector3<f64>,
+    box_max: Vector3<f64>,
+    sides: HitableList,
+}
+
+impl Cuboid {
+    /// 输入两个对角点，顺序任意
+    pub fn new(p0: Vector3<f64>, p1: Vector3<f64>, material: impl Material + 'static) -> Cuboid {
+        Cuboid::with_material(p0, p1, Arc::new(material))
+    }
+
+    /// 六个面共享同一个材质，该材质也可以来自其他物体
+    pub fn with_material(
+        p0: Vector3<f64>,
+        p1: Vector3<f64>,
+        material: Arc<dyn Material>,
+    ) -> Cuboid {
+        let box_min = p0.inf(&p1);
+        let box_max = p0.sup(&p1);
+
+        let mut sides = HitableList::new();
+        sides.push(XYRect::with_material(
+            box_min.x,
+            box_max.x,
+            box_min.y,
+            box_max.y,
+            box_max.z,
+            material.clone(),
+        ));
+        sides.push(FlipFace::new(XYRect::with_material(
+            box_min.x,
+            box_max.x,
+            box_min.y,
+            box_max.y,
+            box_min.z,
+            material.clone(),
+        )));
+        sides.push(XZRect::with_material(
+            box_min.x,
+            box_max.x,
+            box_min.z,
+            box_max.z,
+            box_max.y,
+            material.clone(),
+        ));
+        sides.push(FlipFace::new(XZRect::with_material(
+            box_min.x,
+            box_max.x,
+            box_min.z,
+            box_max.z,
+            box_min.y,
+            material.clone(),
+        )));
+        sides.push(YZRect::with_material(
+            box_min.y,
+            box_max.y,
+            box_min.z,
+            box_max.z,
+            box_max.x,
+            material.clone(),
+        ));
+        sides.push(FlipFace::new(YZRect::with_material(
+            box_min.y, box_max.y, box_min.z, box_max.z, box_min.x, material,
+        )));
+
+        Cuboid {
+            box_min,
+            box_max,
+            sides,
+        }
+    }
+}
+
+impl Hitable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.sides.hit(r, t_min, t_max)
+    }
+    /// 返回长方体本身，不需要加厚
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(AABB::new(self.box_min, self.box_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    #[test]
+    fn bounding_box_matches_the_corners() {
+        let material: Arc<dyn Material> =
+            Arc::new(Lambertian::new(SolidColor::new(Vector3::zeros())));
+        // 对角点的顺序不影响结果
+        let cuboid = Cuboid::with_material(
+            Vector3::new(2.0, -1.0, 3.0),
+            Vector3::new(-1.0, 4.0, 0.5),
+            material.clone(),
+        );
+        let aabb = cuboid.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(aabb.min(), Vector3::new(-1.0, -1.0, 0.5));
+        assert_eq!(aabb.max(), Vector3::new(2.0, 4.0, 3.0));
+
+        // 六个面共享传入的材质，法线朝外
+        for (origin, normal) in [
+            (Vector3::new(0.0, 0.0, 10.0), Vector3::z()),
+            (Vector3::new(0.0, 0.0, -10.0), -Vector3::z()),
+            (Vector3::new(0.0, 10.0, 1.0), Vector3::y()),
+            (Vector3::new(0.0, -10.0, 1.0), -Vector3::y()),
+            (Vector3::new(10.0, 0.0, 1.0), Vector3::x()),
+            (Vector3::new(-10.0, 0.0, 1.0), -Vector3::x()),
+        ] {
+            let target = Vector3::new(0.5, 1.5, 1.75);
+            let ray = Ray::new(origin, (target - origin).normalize(), 0.0, 0.5);
+            let rec = cuboid.hit(&ray, 0.001, f64::MAX).unwrap();
+            assert_eq!(rec.normal(), normal, "from {}", origin);
+            assert!(Arc::ptr_eq(&rec.material(), &material));
+        }
+    }
+}
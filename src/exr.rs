@@ -0,0 +1,329 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::framebuffer::Image;
+
+/// OpenEXR的压缩方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExrCompression {
+    None,
+    /// 每16行一块，zlib压缩
+    Zip,
+}
+
+impl ExrCompression {
+    /// 头部compression属性的取值
+    fn code(self) -> u8 {
+        match self {
+            ExrCompression::None => 0,
+            ExrCompression::Zip => 3,
+        }
+    }
+
+    /// 每块包含的行数
+    fn lines_per_chunk(self) -> usize {
+        match self {
+            ExrCompression::None => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+impl fmt::Display for ExrCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExrCompression::None => "none",
+            ExrCompression::Zip => "zip",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ExrCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ExrCompression::None),
+            "zip" => Ok(ExrCompression::Zip),
+            _ => Err(format!(
+                "unknown EXR compression '{}' (expected none or zip)",
+                s
+            )),
+        }
+    }
+}
+
+/// 头部的一个属性：名字、类型与值
+fn write_attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn i32s(values: &[i32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn f32s(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// 通道按名字排序后为B、G、R，对应像素中的下标
+const CHANNELS: [(&str, usize); 3] = [("B", 2), ("G", 1), ("R", 0)];
+
+fn header(width: usize, height: usize, compression: ExrCompression) -> Vec<u8> {
+    let mut out = Vec::new();
+    // 魔数与版本号2（单部分、扫描线）
+    out.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+    let mut channels = Vec::new();
+    for (name, _) in CHANNELS {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        // 像素类型FLOAT，pLinear与保留字节，x/y方向不降采样
+        channels.extend_from_slice(&i32s(&[2]));
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&i32s(&[1, 1]));
+    }
+    channels.push(0);
+    write_attribute(&mut out, "channels", "chlist", &channels);
+    write_attribute(
+        &mut out,
+        "compression",
+        "compression",
+        &[compression.code()],
+    );
+    let window = i32s(&[0, 0, width as i32 - 1, height as i32 - 1]);
+    write_attribute(&mut out, "dataWindow", "box2i", &window);
+    write_attribute(&mut out, "displayWindow", "box2i", &window);
+    write_attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    write_attribute(&mut out, "pixelAspectRatio", "float", &f32s(&[1.0]));
+    write_attribute(&mut out, "screenWindowCenter", "v2f", &f32s(&[0.0, 0.0]));
+    write_attribute(&mut out, "screenWindowWidth", "float", &f32s(&[1.0]));
+    out.push(0);
+    out
+}
+
+/// 一块扫描线的原始数据：逐行，每行依次为B、G、R通道的全部像素
+fn raw_chunk(image: &Image, lines: std::ops::Range<usize>) -> Vec<u8> {
+    let mut data = Vec::with_capacity(lines.len() * image.width() * 12);
+    for y in lines {
+        let row = image.row(y);
+        for (_, channel) in CHANNELS {
+            for pixel in row {
+                data.extend_from_slice(&pixel[channel].to_le_bytes());
+            }
+        }
+    }
+    data
+}
+
+/// ZIP压缩：字节按奇偶位置拆成两半，做差分预测后用zlib压缩
+/// 压缩后反而更大时按规范直接保存原始数据
+fn zip_chunk(raw: Vec<u8>) -> io::Result<Vec<u8>> {
+    let half = raw.len().div_ceil(2);
+    let mut reordered = vec![0u8; raw.len()];
+    for (i, byte) in raw.iter().enumerate() {
+        let index = if i % 2 == 0 { i / 2 } else { half + i / 2 };
+        reordered[index] = *byte;
+    }
+    let mut previous = reordered.first().copied().unwrap_or(0);
+    for byte in reordered.iter_mut().skip(1) {
+        let current = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&reordered)?;
+    let compressed = encoder.finish()?;
+    Ok(if compressed.len() < raw.len() {
+        compressed
+    } else {
+        raw
+    })
+}
+
+/// 写入单部分扫描线OpenEXR，RGB三个通道均为32位浮点
+pub fn write_exr(
+    out: &mut dyn Write,
+    image: &Image,
+    compression: ExrCompression,
+) -> io::Result<()> {
+    let (width, height) = (image.width(), image.height());
+    let header = header(width, height, compression);
+    let lines_per_chunk = compression.lines_per_chunk();
+
+    let chunks = (0..height)
+        .step_by(lines_per_chunk)
+        .map(|y| {
+            let raw = raw_chunk(image, y..(y + lines_per_chunk).min(height));
+            let data = match compression {
+                ExrCompression::None => raw,
+                ExrCompression::Zip => zip_chunk(raw)?,
+            };
+            Ok((y, data))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // 偏移表记录每一块相对文件开头的位置，块头为行号与数据长度
+    let mut offset = (header.len() + chunks.len() * 8) as u64;
+    out.write_all(&header)?;
+    for (_, data) in &chunks {
+        out.write_all(&offset.to_le_bytes())?;
+        offset += (8 + data.len()) as u64;
+    }
+    for (y, data) in &chunks {
+        out.write_all(&(*y as i32).to_le_bytes())?;
+        out.write_all(&(data.len() as i32).to_le_bytes())?;
+        out.write_all(data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    /// 5x20的平滑图像，ZIP时分为16行与4行两块
+    fn gradient() -> Image {
+        let (width, height) = (5, 20);
+        let pixels = (0..width * height)
+            .map(|i| [(i % width) as f32 * 0.25, (i / width) as f32, 1.5])
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    fn encode(image: &Image, compression: ExrCompression) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_exr(&mut out, image, compression).unwrap();
+        out
+    }
+
+    fn read_i32(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn read_name(bytes: &[u8], at: &mut usize) -> String {
+        let end = *at + bytes[*at..].iter().position(|&b| b == 0).unwrap();
+        let name = String::from_utf8(bytes[*at..end].to_vec()).unwrap();
+        *at = end + 1;
+        name
+    }
+
+    /// 解析头部，返回(名字, 类型, 值)的列表与头部结束的位置
+    fn parse_header(bytes: &[u8]) -> (Vec<(String, String, Vec<u8>)>, usize) {
+        let mut at = 8;
+        let mut attributes = Vec::new();
+        while bytes[at] != 0 {
+            let name = read_name(bytes, &mut at);
+            let kind = read_name(bytes, &mut at);
+            let size = read_i32(bytes, at) as usize;
+            attributes.push((name, kind, bytes[at + 4..at + 4 + size].to_vec()));
+            at += 4 + size;
+        }
+        (attributes, at + 1)
+    }
+
+    /// 按偏移表取出每一块的起始行与数据，并检查块首尾相接、恰好结束于文件末尾
+    fn read_chunks(bytes: &[u8], header_end: usize, count: usize) -> Vec<(i32, &[u8])> {
+        let mut end = header_end + count * 8;
+        let mut chunks = Vec::new();
+        for i in 0..count {
+            let at = header_end + i * 8;
+            let offset = u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap()) as usize;
+            assert_eq!(offset, end, "offset of chunk {}", i);
+            let size = read_i32(bytes, offset + 4) as usize;
+            end = offset + 8 + size;
+            chunks.push((read_i32(bytes, offset), &bytes[offset + 8..end]));
+        }
+        assert_eq!(end, bytes.len(), "data after the last chunk");
+        chunks
+    }
+
+    #[test]
+    fn header_and_offset_table_are_valid() {
+        let image = gradient();
+        for (compression, lines) in [(ExrCompression::None, 1), (ExrCompression::Zip, 16)] {
+            let bytes = encode(&image, compression);
+            assert_eq!(bytes[..4], [0x76, 0x2f, 0x31, 0x01]);
+            assert_eq!(read_i32(&bytes, 4), 2);
+
+            let (attributes, header_end) = parse_header(&bytes);
+            let find = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(n, _, _)| n == name)
+                    .unwrap_or_else(|| panic!("missing {}", name))
+            };
+            for (name, kind) in [
+                ("channels", "chlist"),
+                ("compression", "compression"),
+                ("dataWindow", "box2i"),
+                ("displayWindow", "box2i"),
+                ("lineOrder", "lineOrder"),
+                ("pixelAspectRatio", "float"),
+                ("screenWindowCenter", "v2f"),
+                ("screenWindowWidth", "float"),
+            ] {
+                assert_eq!(find(name).1, kind);
+            }
+            assert_eq!(find("compression").2, [compression.code()]);
+            assert_eq!(find("dataWindow").2, i32s(&[0, 0, 4, 19]));
+
+            let chunks = read_chunks(&bytes, header_end, 20_usize.div_ceil(lines));
+            for (i, (y, _)) in chunks.iter().enumerate() {
+                assert_eq!(*y as usize, i * lines);
+            }
+        }
+    }
+
+    #[test]
+    fn zip_chunk_inflates_to_the_input_floats() {
+        let image = gradient();
+        let bytes = encode(&image, ExrCompression::Zip);
+        let (_, header_end) = parse_header(&bytes);
+        for (y, data) in read_chunks(&bytes, header_end, 2) {
+            let y = y as usize;
+            let lines = y..(y + 16).min(image.height());
+            let raw = raw_chunk(&image, lines.clone());
+            assert!(data.len() < raw.len(), "chunk at {} was not compressed", y);
+
+            let mut predicted = Vec::new();
+            ZlibDecoder::new(data).read_to_end(&mut predicted).unwrap();
+            // 撤销差分预测，再把前后两半交错回原来的顺序
+            for i in 1..predicted.len() {
+                predicted[i] = predicted[i - 1]
+                    .wrapping_add(predicted[i])
+                    .wrapping_sub(128);
+            }
+            let half = predicted.len().div_ceil(2);
+            let decoded: Vec<u8> = (0..predicted.len())
+                .map(|i| predicted[if i % 2 == 0 { i / 2 } else { half + i / 2 }])
+                .collect();
+
+            // 每行依次是B、G、R通道
+            let mut floats = decoded
+                .chunks(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()));
+            for line in lines {
+                for (_, channel) in CHANNELS {
+                    for pixel in image.row(line) {
+                        assert_eq!(floats.next(), Some(pixel[channel]));
+                    }
+                }
+            }
+            assert_eq!(floats.next(), None);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use crate::filter::Filter;
+use crate::framebuffer::Image;
+
+/// 一个像素累加的加权颜色与权重
+#[derive(Clone, Copy, Default)]
+struct FilmPixel {
+    sum: Vector3<f64>,
+    weight: f64,
+}
+
+/// 胶片：用重建滤波器把采样累加到附近的像素
+/// 坐标为光栅坐标，x向右、y向下，像素(x, y)的中心在(x + 0.5, y + 0.5)
+pub struct Film {
+    width: usize,
+    height: usize,
+    filter: Arc<dyn Filter>,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    /// width × height个像素的空胶片，采样按filter累加
+    pub fn new(width: usize, height: usize, filter: Box<dyn Filter>) -> Film {
+        Film {
+            width,
+            height,
+            filter: Arc::from(filter),
+            pixels: vec![FilmPixel::default(); width * height],
+        }
+    }
+
+    /// 为渲染xs × ys内像素的采样创建一块胶片
+    /// 采样会影响滤波半径内的相邻像素，所以这一块覆盖的像素比xs × ys更大
+    /// 各块可以并行累加，之后再用merge_tile合并
+    pub fn tile(&self, xs: Range<usize>, ys: Range<usize>) -> FilmTile {
+        let margin = (self.filter.radius() + 0.5).floor() as usize;
+        let xs = xs.start.saturating_sub(margin)..(xs.end + margin).min(self.width);
+        let ys = ys.start.saturating_sub(margin)..(ys.end + margin).min(self.height);
+        FilmTile {
+            filter: Arc::clone(&self.filter),
+            pixels: vec![FilmPixel::default(); xs.len() * ys.len()],
+            xs,
+            ys,
+        }
+    }
+
+    /// 把一块胶片累加进来
+    /// 按固定的顺序合并才能保证结果与线程数无关
+    pub fn merge_tile(&mut self, tile: FilmTile) {
+        let tile_width = tile.xs.len();
+        for (row, y) in tile.ys.clone().enumerate() {
+            let src = &tile.pixels[row * tile_width..(row + 1) * tile_width];
+            let dst = &mut self.pixels[y * self.width + tile.xs.start..][..tile_width];
+            for (d, s) in dst.iter_mut().zip(src) {
+                d.sum += s.sum;
+                d.weight += s.weight;
+            }
+        }
+    }
+
+    /// 每个像素的加权平均；没有收到任何权重的像素为黑色
+    pub fn into_image(self) -> Image {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|p| {
+                if p.weight != 0.0 {
+                    (p.sum / p.weight).map(|c| c as f32).into()
+                } else {
+                    [0.0; 3]
+                }
+            })
+            .collect();
+        Image::from_pixels(self.width, self.height, pixels)
+    }
+}
+
+/// 胶片的一块，独立累加采样
+pub struct FilmTile {
+    filter: Arc<dyn Filter>,
+    xs: Range<usize>,
+    ys: Range<usize>,
+    pixels: Vec<FilmPixel>,
+}
+
+impl FilmTile {
+    /// 在光栅坐标(x, y)处加入一个采样，颜色按滤波器权重累加到半径内的每个像素
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Vector3<f64>) {
+        let radius = self.filter.radius();
+        // 像素中心与采样的距离不超过半径的像素范围，裁剪到这一块内
+        let pixel_range = |p: f64, range: &Range<usize>| {
+            let lo = (p - 0.5 - radius).ceil().max(range.start as f64) as usize;
+            let hi = ((p - 0.5 + radius).floor() + 1.0).min(range.end as f64);
+            lo..(hi.max(lo as f64) as usize)
+        };
+        let tile_width = self.xs.len();
+        for py in pixel_range(y, &self.ys) {
+            for px in pixel_range(x, &self.xs) {
+                let weight = self
+                    .filter
+                    .evaluate(px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                if weight != 0.0 {
+                    let pixel =
+                        &mut self.pixels[(py - self.ys.start) * tile_width + px - self.xs.start];
+                    pixel.sum += weight * color;
+                    pixel.weight += weight;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{BoxFilter, TentFilter};
+
+    /// 整幅胶片作为一块，加入采样后合并
+    fn develop(
+        filter: impl Filter + 'static,
+        width: usize,
+        height: usize,
+        samples: &[(f64, f64, Vector3<f64>)],
+    ) -> Image {
+        let mut film = Film::new(width, height, Box::new(filter));
+        let mut tile = film.tile(0..width, 0..height);
+        for &(x, y, color) in samples {
+            tile.add_sample(x, y, color);
+        }
+        film.merge_tile(tile);
+        film.into_image()
+    }
+
+    #[test]
+    fn box_filter_averages_samples_within_each_pixel() {
+        let red = Vector3::new(1.0, 0.0, 0.0);
+        let blue = Vector3::new(0.0, 0.0, 1.0);
+        let image = develop(
+            BoxFilter::new(0.5),
+            2,
+            1,
+            &[
+                (0.1, 0.5, red),
+                (0.9, 0.2, blue),
+                (0.5, 0.99, blue),
+                (1.01, 0.5, red),
+                (1.99, 0.01, red),
+            ],
+        );
+        let expected: [[f32; 3]; 2] = [[1.0 / 3.0, 0.0, 2.0 / 3.0], [1.0, 0.0, 0.0]];
+        for (pixel, expected) in image.pixels().iter().zip(expected) {
+            for (c, e) in pixel.iter().zip(expected) {
+                assert!((c - e).abs() < 1e-6, "{:?}", image.pixels());
+            }
+        }
+    }
+
+    #[test]
+    fn samples_near_a_border_reach_the_neighbours() {
+        // 半径1的三角形滤波器，(1.1, 0.5)到像素0、1、2中心的偏移为-0.6、0.4、1.4
+        // 对应权重0.4、0.6、0；像素1另有一个位于中心、权重为1的黑色采样
+        let white = Vector3::new(1.0, 1.0, 1.0);
+        let image = develop(
+            TentFilter::new(1.0),
+            3,
+            1,
+            &[(1.1, 0.5, white), (1.5, 0.5, Vector3::zeros())],
+        );
+        let values: Vec<f32> = image.pixels().iter().map(|p| p[0]).collect();
+        assert!((values[0] - 1.0).abs() < 1e-6, "{:?}", values);
+        assert!((values[1] - 0.6 / 1.6).abs() < 1e-6, "{:?}", values);
+        assert_eq!(values[2], 0.0);
+    }
+
+    /// 中心左侧权重为-1、右侧为1，用来构造权重恰好抵消的像素
+    struct SignedFilter;
+
+    impl Filter for SignedFilter {
+        fn radius(&self) -> f64 {
+            1.0
+        }
+        fn evaluate(&self, x: f64, _y: f64) -> f64 {
+            if x.abs() >= 1.0 {
+                0.0
+            } else {
+                x.signum()
+            }
+        }
+    }
+
+    #[test]
+    fn pixels_whose_weights_cancel_stay_black() {
+        // 像素0的两个采样权重为+1与-1，像素2没有收到任何采样
+        let image = develop(
+            SignedFilter,
+            3,
+            1,
+            &[
+                (0.25, 0.5, Vector3::new(1.0, 2.0, 3.0)),
+                (0.75, 0.5, Vector3::new(3.0, 2.0, 1.0)),
+            ],
+        );
+        for pixel in [image.pixels()[0], image.pixels()[2]] {
+            assert_eq!(pixel, [0.0; 3]);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// 像素重建滤波器
+/// 采样按滤波器权重累加到半径内的所有像素，x、y为采样到像素中心的偏移，单位为像素
+pub trait Filter: Send + Sync {
+    fn radius(&self) -> f64;
+    /// 偏移超出半径时应返回0
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
+
+/// 滤波器种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterKind {
+    /// 未指定半径时使用的默认值
+    pub fn default_radius(self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+
+    /// radius：滤波器半径，单位为像素，必须大于0
+    pub fn create(self, radius: f64) -> Box<dyn Filter> {
+        match self {
+            FilterKind::Box => Box::new(BoxFilter::new(radius)),
+            FilterKind::Tent => Box::new(TentFilter::new(radius)),
+            FilterKind::Gaussian => Box::new(GaussianFilter::new(radius)),
+            FilterKind::Mitchell => Box::new(MitchellFilter::new(radius)),
+            FilterKind::Lanczos => Box::new(LanczosFilter::new(radius)),
+        }
+    }
+}
+
+impl fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FilterKind::Box => "box",
+            FilterKind::Tent => "tent",
+            FilterKind::Gaussian => "gaussian",
+            FilterKind::Mitchell => "mitchell",
+            FilterKind::Lanczos => "lanczos",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for FilterKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(FilterKind::Box),
+            "tent" => Ok(FilterKind::Tent),
+            "gaussian" => Ok(FilterKind::Gaussian),
+            "mitchell" => Ok(FilterKind::Mitchell),
+            "lanczos" => Ok(FilterKind::Lanczos),
+            _ => Err(format!(
+                "unknown filter '{}' (expected box, tent, gaussian, mitchell or lanczos)",
+                s
+            )),
+        }
+    }
+}
+
+/// 盒式滤波器，半径0.5时等价于对像素内的采样取平均
+/// 与其他滤波器一样，恰好在半径处的权重为0
+pub struct BoxFilter {
+    radius: f64,
+}
+
+impl BoxFilter {
+    /// radius为0.5时只覆盖采样所在的像素
+    pub fn new(radius: f64) -> BoxFilter {
+        BoxFilter { radius }
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        if x.abs() < self.radius && y.abs() < self.radius {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 三角形滤波器，权重随距离线性下降
+pub struct TentFilter {
+    radius: f64,
+}
+
+impl TentFilter {
+    /// 权重在距离radius处降为0
+    pub fn new(radius: f64) -> TentFilter {
+        TentFilter { radius }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.0) * (self.radius - y.abs()).max(0.0)
+    }
+}
+
+/// 高斯滤波器，标准差为半径的1/3，减去半径处的值使边缘平滑降到0
+pub struct GaussianFilter {
+    radius: f64,
+    sigma: f64,
+    edge: f64,
+}
+
+impl GaussianFilter {
+    /// 支撑半径radius，标准差随之取radius / 3
+    pub fn new(radius: f64) -> GaussianFilter {
+        let sigma = radius / 3.0;
+        GaussianFilter {
+            radius,
+            sigma,
+            edge: gaussian(radius, sigma),
+        }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        (gaussian(x, self.sigma) - self.edge).max(0.0)
+    }
+}
+
+fn gaussian(x: f64, sigma: f64) -> f64 {
+    (-x * x / (2.0 * sigma * sigma)).exp()
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+/// Mitchell-Netravali滤波器，B = C = 1/3
+/// 原始定义的支撑为[-2, 2]，按半径缩放
+pub struct MitchellFilter {
+    radius: f64,
+}
+
+impl MitchellFilter {
+    const B: f64 = 1.0 / 3.0;
+    const C: f64 = 1.0 / 3.0;
+
+    /// 默认半径2与原始定义一致
+    pub fn new(radius: f64) -> MitchellFilter {
+        MitchellFilter { radius }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let (b, c) = (Self::B, Self::C);
+        let x = (2.0 * x / self.radius).abs();
+        let value = if x < 1.0 {
+            (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b)
+        } else if x < 2.0 {
+            (-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c)
+        } else {
+            0.0
+        };
+        value / 6.0
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+/// Lanczos滤波器：sinc(x)·sinc(x / radius)，半径即瓣数
+pub struct LanczosFilter {
+    radius: f64,
+}
+
+impl LanczosFilter {
+    /// radius同时是窗口sinc的宽度，常用2或3
+    pub fn new(radius: f64) -> LanczosFilter {
+        LanczosFilter { radius }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        if x.abs() >= self.radius {
+            0.0
+        } else {
+            sinc(x) * sinc(x / self.radius)
+        }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [FilterKind; 5] = [
+        FilterKind::Box,
+        FilterKind::Tent,
+        FilterKind::Gaussian,
+        FilterKind::Mitchell,
+        FilterKind::Lanczos,
+    ];
+
+    #[test]
+    fn filters_vanish_at_and_beyond_the_radius() {
+        for kind in KINDS {
+            for radius in [0.5, kind.default_radius(), 2.5] {
+                let filter = kind.create(radius);
+                assert!(filter.evaluate(0.0, 0.0) > 0.0, "{} at the centre", kind);
+                for d in [radius, radius + 1e-9, radius * 1.5, radius + 10.0] {
+                    for (x, y) in [(d, 0.0), (-d, 0.0), (0.0, d), (0.0, -d), (d, d)] {
+                        assert_eq!(
+                            filter.evaluate(x, y),
+                            0.0,
+                            "{} radius {} at ({}, {})",
+                            kind,
+                            radius,
+                            x,
+                            y
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
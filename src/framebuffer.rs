@@ -0,0 +1,40 @@
+/// 渲染结果：线性RGB浮点图像（帧缓冲），保留HDR信息
+/// 像素按行保存，第0行是图像最上面一行
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Image {
+    /// pixels：从上到下、从左到右，长度必须为width * height
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<[f32; 3]>) -> Image {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "image size does not match its dimensions"
+        );
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// 宽度，单位为像素
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// 全部像素，从上到下、从左到右
+    pub fn pixels(&self) -> &[[f32; 3]] {
+        &self.pixels
+    }
+
+    /// 第y行（从上往下数）的像素
+    pub fn row(&self, y: usize) -> &[[f32; 3]] {
+        &self.pixels[y * self.width..(y + 1) * self.width]
+    }
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use super::ray::Ray;
 
+/// 光线与物体的一次相交
 pub struct HitRecord {
     point: Vector3<f64>,         //交点
     normal: Vector3<f64>,        //交点法线
@@ -14,9 +15,12 @@ pub struct HitRecord {
     u: f64,                      //uv坐标系横坐标
     v: f64,                      //uv坐标系纵坐标
     material: Arc<dyn Material>, //命中材质
+    color: Option<Vector3<f64>>, //插值得到的顶点颜色
 }
 
 impl HitRecord {
+    /// point：交点，normal：交点处的法线，time：交点对应的光线参数t
+    /// u、v：交点的纹理坐标
     pub fn new(
         point: Vector3<f64>,
         normal: Vector3<f64>,
@@ -32,8 +36,27 @@ impl HitRecord {
             material,
             u,
             v,
+            color: None,
         }
     }
+
+    /// 附上交点处插值得到的顶点颜色
+    pub fn with_vertex_color(self, color: Vector3<f64>) -> HitRecord {
+        HitRecord {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    /// 换到另一个空间中的交点与法线，材质、uv与顶点颜色保持不变
+    pub fn moved(self, point: Vector3<f64>, normal: Vector3<f64>) -> HitRecord {
+        HitRecord {
+            point,
+            normal,
+            ..self
+        }
+    }
+
     pub fn normal(&self) -> Vector3<f64> {
         self.normal
     }
@@ -52,13 +75,32 @@ impl HitRecord {
     pub fn v(&self) -> f64 {
         self.v
     }
+    /// 只有带顶点颜色的网格会给出
+    pub fn vertex_color(&self) -> Option<Vector3<f64>> {
+        self.color
+    }
 }
 
+/// 可被光线命中的物体
 pub trait Hitable: Sync + Send {
+    /// 光线参数在(t_min, t_max)内的最近交点
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    /// time0到time1之间的包围盒，无界的物体返回None
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB>;
 }
 
+/// 装箱的物体，使运行时组合的物体也能被平移、旋转等包装
+impl<H: Hitable + ?Sized> Hitable for Box<H> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.as_ref().hit(r, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.as_ref().bounding_box(time0, time1)
+    }
+}
+
+/// 物体列表，逐个求交
+#[derive(Default)]
 pub struct HitableList(Vec<Box<dyn Hitable>>);
 
 impl HitableList {
@@ -66,11 +108,18 @@ impl HitableList {
         self.0.push(Box::new(hitable));
     }
 
+    /// 空列表
     pub fn new() -> HitableList {
         HitableList(Vec::new())
     }
 }
 
+impl From<HitableList> for Vec<Box<dyn Hitable>> {
+    fn from(list: HitableList) -> Self {
+        list.0
+    }
+}
+
 impl Hitable for HitableList {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let mut closest_so_far = t_max;
@@ -83,14 +132,35 @@ impl Hitable for HitableList {
         }
         hit_anything
     }
-    /// 计算所有物体的包围盒
+    /// 所有物体包围盒的并；列表为空或其中有无界的物体时为None
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
-        let mut outbox = self.0[0].bounding_box(time0, time1).unwrap();
-        for obj in &self.0 {
-            if let Some(objbox) = obj.bounding_box(time0, time1) {
-                outbox = surrounding_box(&outbox, &objbox);
-            }
-        }
-        Some(outbox)
+        let mut boxes = self.0.iter().map(|obj| obj.bounding_box(time0, time1));
+        let first = boxes.next()??;
+        boxes.try_fold(first, |outbox, objbox| {
+            Some(surrounding_box(&outbox, &objbox?))
+        })
+    }
+}
+
+/// 翻转被包裹物体的法线方向
+/// 用于让平面物体的法线朝向需要的一侧
+pub struct FlipFace<T: Hitable>(T);
+
+impl<T: Hitable> FlipFace<T> {
+    /// 翻转hitable的法线
+    pub fn new(hitable: T) -> FlipFace<T> {
+        FlipFace(hitable)
+    }
+}
+
+impl<T: Hitable> Hitable for FlipFace<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.0.hit(r, t_min, t_max).map(|rec| HitRecord {
+            normal: -rec.normal,
+            ..rec
+        })
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.0.bounding_box(time0, time1)
     }
 }
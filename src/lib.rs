@@ -0,0 +1,60 @@
+//! 一个小型路径追踪器
+//!
+//! 用[`Scene`]描述物体、背景与相机，用[`RenderSettings`]描述图像与采样参数，
+//! 再调用[`render()`]得到线性HDR的[`Image`]：
+//!
+//! ```no_run
+//! use restart_raytrace::{render, scenes, RenderRng, RenderSettings};
+//!
+//! let settings = RenderSettings::default();
+//! let mut rng = RenderRng::from_seed(settings.seed);
+//! let build = scenes::find("cornell-box").unwrap();
+//! let scene = build(settings.image.aspect_ratio, &mut rng)?;
+//! let image = render(&scene, &settings)?;
+//! println!("{} x {}", image.width(), image.height());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+#![allow(clippy::upper_case_acronyms)]
+
+pub mod aabb;
+pub mod background;
+pub mod bvh;
+pub mod camera;
+pub mod cuboid;
+pub mod exr;
+pub mod film;
+pub mod filter;
+pub mod framebuffer;
+pub mod hitable;
+pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod obj;
+pub mod output;
+pub mod perlin;
+pub mod ply;
+pub mod progress;
+pub mod ray;
+pub mod rect;
+pub mod render;
+pub mod rng;
+pub mod sampler;
+pub mod scene;
+pub mod scene_file;
+pub mod scenes;
+pub mod sphere;
+pub mod texture;
+pub mod tile;
+pub mod tonemap;
+pub mod transform;
+pub mod triangle;
+
+pub use crate::bvh::{BVHError, BVH};
+pub use crate::camera::Camera;
+pub use crate::framebuffer::Image;
+pub use crate::hitable::{Hitable, HitableList};
+pub use crate::material::Material;
+pub use crate::render::{render, RenderError, RenderSettings, Renderer};
+pub use crate::rng::RenderRng;
+pub use crate::scene::{ImageSettings, Scene};
+pub use crate::texture::Texture;
@@ -1,198 +1,85 @@
-mod aabb;
-mod bvh;
-mod camera;
-mod hitable;
-mod material;
-mod perlin;
-mod ray;
-mod sphere;
-mod texture;
+mod cli;
 
-use bvh::BVH;
-use camera::Camera;
-use hitable::{Hitable, HitableList};
-use material::{Dielectric, Lambertian, Metal};
-use na::Vector3;
-use nalgebra as na;
-use rand::Rng;
-use ray::Ray;
-use rayon::prelude::*;
-use sphere::{MovingSphere, Sphere};
-use texture::{CheckerTexture, NoiseTexture, SolidColor};
+use std::path::Path;
 
-// 返回BVH树的根节点，Box<BVH>
-#[allow(dead_code)]
-fn random_scene() -> Box<dyn Hitable> {
-    let mut rng = rand::thread_rng();
-    let origin = Vector3::new(4.0, 0.2, 0.0);
-    let mut world: Vec<Box<dyn Hitable>> = Vec::new();
-    let checker = CheckerTexture::new(
-        SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
-        SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
-    );
-    world.push(Box::new(Sphere::new(
-        Vector3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Lambertian::new(checker),
-    )));
+use clap::Parser;
+use restart_raytrace::output::{output_format, write_image};
+use restart_raytrace::progress::ProgressReporter;
+use restart_raytrace::scene_file::load_scene;
+use restart_raytrace::{scenes, ImageSettings, RenderRng, RenderSettings, Renderer};
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_material = rng.gen::<f64>();
-            let center = Vector3::new(
-                a as f64 + 0.9 * rng.gen::<f64>(),
-                0.2,
-                b as f64 + 0.9 * rng.gen::<f64>(),
-            );
-            if (center - origin).magnitude() > 0.9 {
-                if choose_material < 0.8 {
-                    // diffuse
-                    world.push(Box::new(MovingSphere::new(
-                        center,
-                        center + Vector3::new(0.0, rng.gen_range(0.0..0.5), 0.0),
-                        0.0,
-                        1.0,
-                        0.2,
-                        Lambertian::new(SolidColor::new(Vector3::new(
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                        ))),
-                    )));
-                } else if choose_material < 0.95 {
-                    // metal
-                    world.push(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Metal::new(
-                            Vector3::new(
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                            ),
-                            0.5 * rng.gen::<f64>(),
-                        ),
-                    )));
-                } else {
-                    // glass
-                    world.push(Box::new(Sphere::new(center, 0.2, Dielectric::new(1.5))));
-                }
-            }
-        }
-    }
-    world.push(Box::new(Sphere::new(
-        Vector3::new(0.0, 1.0, 0.0),
-        1.0,
-        Dielectric::new(1.5),
-    )));
-    world.push(Box::new(Sphere::new(
-        Vector3::new(-4.0, 1.0, 0.0),
-        1.0,
-        Lambertian::new(SolidColor::new(Vector3::new(0.4, 0.2, 0.1))),
-    )));
-    world.push(Box::new(Sphere::new(
-        Vector3::new(4.0, 1.0, 0.0),
-        1.0,
-        Metal::new(Vector3::new(0.7, 0.6, 0.5), 0.0),
-    )));
-    Box::new(BVH::new(world, 0.0, 1.0))
-}
+use cli::Args;
 
-fn two_spheres() -> impl Hitable {
-    // let texture1 = CheckerTexture::new(
-    //   SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
-    //   SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
-    // );
-    let texture1 = NoiseTexture::new(4.0);
-    let texture2 = NoiseTexture::new(4.0);
-
-    let mut world = HitableList::new();
-    world.push(Sphere::new(
-        Vector3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Lambertian::new(texture1),
-    ));
-    world.push(Sphere::new(
-        Vector3::new(0.0, 2.0, 0.0),
-        2.0,
-        Lambertian::new(texture2),
-    ));
-    world
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
 }
 
-fn ray_color(r: Ray, world: &dyn Hitable, depth: usize) -> Vector3<f64> {
-    if depth == 0 {
-        return Vector3::new(0.0, 0.0, 0.0);
-    };
-    if let Some(rec) = world.hit(&r, 0.001, f64::MAX) {
-        if let Some((sactter, albedo)) = rec.material().scatter(&r, &rec) {
-            return albedo.component_mul(&ray_color(sactter, world, depth - 1));
+fn main() {
+    let args = Args::parse();
+    if args.list_scenes {
+        for (name, _) in scenes::SCENES {
+            println!("{}", name);
         }
-        Vector3::new(0.0, 0.0, 0.0)
-    } else {
-        let unit_direction: Vector3<f64> = r.direction().normalize();
-        let t = 0.5 * (unit_direction[1] + 1.0);
-        (1.0 - t) * Vector3::new(1.0, 1.0, 1.0) + t * Vector3::new(0.5, 0.7, 1.0)
+        return;
     }
-}
-
-fn main() {
-    // 图像参数
-    const IMAGE_WIDTH: usize = 600;
-    const IMAGE_HEIGHT: usize = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as usize;
-    const ASPECT_RATIO: f64 = 3.0 / 2.0;
-    const SAMPLES_PER_PIXEL: usize = 20;
-    const MAX_DEPTH: usize = 5;
-
-    //物体
-    let world = two_spheres();
-
-    //相机
-    let camera = Camera::new(
-        Vector3::new(13.0, 2.0, 3.0),
-        Vector3::new(0.0, 0.0, 0.0),
-        Vector3::new(0.0, 1.0, 0.0),
-        20.0,
-        ASPECT_RATIO,
-        0.1,
-        10.0,
-        0.0,
-        1.0,
-    );
-
-    // 计算像素颜色
-    let image: Vec<u8> = (0..IMAGE_HEIGHT)
-        .into_par_iter()
-        .rev()
-        .flat_map(|image_y| {
-            (0..IMAGE_WIDTH)
-                .into_par_iter()
-                .flat_map(|image_x| {
-                    let mut color: Vector3<f64> = Vector3::new(0.0, 0.0, 0.0);
-                    let mut rng = rand::thread_rng();
-                    for _ in 0..SAMPLES_PER_PIXEL {
-                        let u = ((image_x as f64 + rng.gen::<f64>()) / (IMAGE_WIDTH as f64 - 1.0))
-                            .min(1.0);
-                        let v = ((image_y as f64 + rng.gen::<f64>()) / (IMAGE_HEIGHT as f64 - 1.0))
-                            .min(1.0);
+    if args.threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global()
+            .unwrap_or_else(|e| fail(e));
+    }
+    let seed = args.seed;
+    let format = output_format(&args.output, args.format).unwrap_or_else(|e| fail(e));
 
-                        color += ray_color(camera.get_ray(u, v), &world, MAX_DEPTH);
-                    }
-                    color
-                        .iter()
-                        .map(|f| (((*f / SAMPLES_PER_PIXEL as f64).sqrt() * 255.99) as u8))
-                        .collect::<Vec<u8>>()
-                })
-                .collect::<Vec<u8>>()
-        })
-        .collect::<Vec<u8>>();
+    //物体与相机：内置场景按名字查找，否则视为场景文件
+    let mut rng = RenderRng::from_seed(seed);
+    let overrides = args.image_overrides();
+    let (image_settings, scene) = match scenes::find(&args.scene) {
+        Some(build) => {
+            let mut image_settings = ImageSettings::default();
+            overrides.apply(&mut image_settings);
+            let scene = build(image_settings.aspect_ratio, &mut rng).unwrap_or_else(|e| fail(e));
+            (image_settings, scene)
+        }
+        // 既不是内置场景，也不像文件路径
+        None if Path::new(&args.scene).extension().is_none() => fail(format!(
+            "unknown scene '{}' (run with --list-scenes to see the built-in scenes)",
+            args.scene
+        )),
+        None => match load_scene(&args.scene, &overrides, &mut rng) {
+            Ok(file) => (file.image, file.scene),
+            Err(e) => fail(e),
+        },
+    };
+    // 场景最外层BVH的构建时间，不含网格内部的BVH
+    if !args.quiet {
+        eprintln!("BVH built in {:.1?}", scene.world.build_time());
+    }
 
-    //打印
-    println!("P3");
-    println!("{} {}", IMAGE_WIDTH, IMAGE_HEIGHT);
-    println!("255");
+    let settings = RenderSettings {
+        image: image_settings,
+        seed,
+        sampler: args.sampler,
+        filter: args.filter,
+        filter_radius: args.filter_radius(),
+        tile_size: args.tile_size,
+        tile_order: args.tile_order,
+    };
 
-    for p in image.chunks(3) {
-        println!("{} {} {}", p[0], p[1], p[2]);
+    let progress = ProgressReporter::new();
+    let mut renderer = Renderer::new(&scene, &settings);
+    if !args.quiet {
+        renderer = renderer.on_tile(|tile| progress.update(tile));
     }
+    let image = renderer.render().unwrap_or_else(|e| fail(e));
+
+    write_image(
+        &args.output,
+        format,
+        &image,
+        &args.tone_mapping(),
+        args.exr_compression,
+    )
+    .unwrap_or_else(|e| fail(e));
 }
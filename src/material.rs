@@ -1,20 +1,23 @@
+use std::sync::Arc;
+
 use crate::texture::Texture;
 
 use super::hitable::HitRecord;
 use super::ray::Ray;
+use super::sampler::Sampler;
 use nalgebra::Vector3;
-use rand::Rng;
-
-/// 生成一个长度小于1的随机向量
-pub fn random_in_unit_sphere() -> Vector3<f64> {
-    let mut rng = rand::thread_rng();
-    let unit = Vector3::new(1.0, 1.0, 1.0);
-    loop {
-        let p = 2.0 * Vector3::new(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()) - unit;
-        if p.magnitude_squared() < 1.0 {
-            return p;
-        }
-    }
+use std::f64::consts::PI;
+
+/// 生成一个长度小于1的随机向量，在单位球内均匀分布
+/// 不用拒绝采样，每次固定消耗采样器的三个维度
+pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vector3<f64> {
+    let (u1, u2) = sampler.get_2d();
+    let u3 = sampler.get_1d();
+    // 先在球面上均匀取方向，再按体积均匀取半径
+    let z = 1.0 - 2.0 * u1;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    u3.cbrt() * Vector3::new(r * phi.cos(), r * phi.sin(), z)
 }
 
 /// 反射
@@ -37,15 +40,39 @@ fn refract(v: &Vector3<f64>, n: &Vector3<f64>, ni_over_nt: f64) -> Option<Vector
     }
 }
 
+/// 材质：决定光线在交点处如何散射与发光
 pub trait Material: Sync + Send {
-    /// 输入：入射光、命中信息、衰减率
+    /// 输入：入射光、命中信息、该采样的采样器
     /// 输出：出射光、衰减率
     fn scatter(
         &self,
         r_in: &Ray,
         hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
         //attenuation: Vector3<f64>,
     ) -> Option<(Ray, Vector3<f64>)>;
+
+    /// 自发光，输入命中点的uv坐标与位置
+    /// 默认不发光
+    fn emitted(&self, _u: f64, _v: f64, _p: Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(0.0, 0.0, 0.0)
+    }
+}
+
+/// 共享材质，使多个物体可以引用同一个材质
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
+        self.as_ref().scatter(r_in, hit_record, sampler)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        self.as_ref().emitted(u, v, p)
+    }
 }
 
 /// Lambertian材质
@@ -55,6 +82,7 @@ pub struct Lambertian {
 }
 
 impl Lambertian {
+    /// albedo：各处的反射率
     pub fn new(albedo: impl Texture + 'static) -> Lambertian {
         Lambertian {
             albedo: Box::new(albedo),
@@ -68,13 +96,17 @@ impl Material for Lambertian {
         &self,
         r_in: &Ray,
         hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
         //attenuation: Vector3<f64>,
     ) -> Option<(Ray, Vector3<f64>)> {
-        let scatter_direction = hit_record.normal() + random_in_unit_sphere();
-        let sactter = Ray::new(hit_record.point(), scatter_direction, r_in.time());
-        let attenuation = self
-            .albedo
-            .value(hit_record.u(), hit_record.v(), hit_record.point());
+        let scatter_direction = hit_record.normal() + random_in_unit_sphere(sampler);
+        let sactter = Ray::new(
+            hit_record.point(),
+            scatter_direction,
+            r_in.time(),
+            sampler.get_1d(),
+        );
+        let attenuation = self.albedo.value_at(hit_record);
         Some((sactter, attenuation))
     }
 }
@@ -87,6 +119,7 @@ pub struct Metal {
 }
 
 impl Metal {
+    /// fuzz：反射方向的模糊程度，0为理想镜面，大于1时按1处理
     pub fn new(albedo: Vector3<f64>, fuzz: f64) -> Metal {
         Metal {
             albedo,
@@ -100,13 +133,19 @@ impl Material for Metal {
         &self,
         r_in: &Ray,
         hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
         //attenuation: Vector3<f64>,
     ) -> Option<(Ray, Vector3<f64>)> {
         let reflected_direction = reflect(&r_in.direction().normalize(), &hit_record.normal())
-            + self.fuzz * random_in_unit_sphere();
+            + self.fuzz * random_in_unit_sphere(sampler);
         if reflected_direction.dot(&hit_record.normal()) > 0.0 {
             //加了模糊反射后在表面外
-            let sactter: Ray = Ray::new(hit_record.point(), reflected_direction, r_in.time());
+            let sactter: Ray = Ray::new(
+                hit_record.point(),
+                reflected_direction,
+                r_in.time(),
+                sampler.get_1d(),
+            );
             Some((sactter, self.albedo))
         } else {
             None
@@ -114,23 +153,30 @@ impl Material for Metal {
     }
 }
 
+/// 电介质（玻璃、水），按Schlick近似在反射与折射之间随机选择
 pub struct Dielectric {
     refraction_index: f64,
 }
 
 impl Dielectric {
+    /// refraction_index：相对于外部介质的折射率，玻璃约为1.5
     pub fn new(refraction_index: f64) -> Dielectric {
         Dielectric { refraction_index }
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
         let attenuation: Vector3<f64> = Vector3::new(1.0, 1.0, 1.0); // 无损失
         let (outward_normal, ni_over_nt, cos_theta) =
             if r_in.direction().dot(&hit_record.normal()) > 0.0 {
                 // 法线与入射光同侧，计算物体内表面的折射参数
-                let outward_normal = -1.0 * hit_record.normal();
+                let outward_normal = -hit_record.normal();
                 let ni_over_nt = self.refraction_index;
                 let cos_theta = ni_over_nt * r_in.direction().normalize().dot(&hit_record.normal());
                 (outward_normal, ni_over_nt, cos_theta)
@@ -138,7 +184,7 @@ impl Material for Dielectric {
                 // 法线与入射光起点同侧，计算物体外表面的折射参数
                 let outward_normal = hit_record.normal();
                 let ni_over_nt = 1.0 / self.refraction_index;
-                let cos_theta = -1.0 * r_in.direction().normalize().dot(&hit_record.normal());
+                let cos_theta = -r_in.direction().normalize().dot(&hit_record.normal());
                 (outward_normal, ni_over_nt, cos_theta)
             };
 
@@ -149,9 +195,9 @@ impl Material for Dielectric {
             let reflectance_out = // 反射率
             reflectance_in + (1.0 - reflectance_in) * (1.0 - cos_theta).powi(5);
 
-            if rand::thread_rng().gen::<f64>() > reflectance_out {
+            if sampler.get_1d() > reflectance_out {
                 return Some((
-                    Ray::new(hit_record.point(), refracted, r_in.time()),
+                    Ray::new(hit_record.point(), refracted, r_in.time(), sampler.get_1d()),
                     attenuation,
                 ));
             }
@@ -162,8 +208,74 @@ impl Material for Dielectric {
             hit_record.point(),
             reflect(&r_in.direction().normalize(), &hit_record.normal()),
             r_in.time(),
+            sampler.get_1d(),
         );
 
         Some((scattered, attenuation))
     }
 }
+
+/// 漫射光源材质
+/// emit：发光颜色，由纹理决定
+pub struct DiffuseLight {
+    emit: Box<dyn Texture>,
+}
+
+impl DiffuseLight {
+    /// emit的值可以大于1
+    pub fn new(emit: impl Texture + 'static) -> DiffuseLight {
+        DiffuseLight {
+            emit: Box::new(emit),
+        }
+    }
+}
+
+/// 光源不散射，只发光
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        self.emit.value(u, v, p)
+    }
+}
+
+/// 各向同性相函数，用于参与介质
+/// albedo：散射的衰减率
+pub struct Isotropic {
+    albedo: Box<dyn Texture>,
+}
+
+impl Isotropic {
+    /// 向各个方向均匀散射，albedo为衰减率
+    pub fn new(albedo: impl Texture + 'static) -> Isotropic {
+        Isotropic {
+            albedo: Box::new(albedo),
+        }
+    }
+}
+
+/// 向任意方向均匀散射
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
+        let scattered = Ray::new(
+            hit_record.point(),
+            random_in_unit_sphere(sampler),
+            r_in.time(),
+            sampler.get_1d(),
+        );
+        let attenuation = self.albedo.value_at(hit_record);
+        Some((scattered, attenuation))
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+use crate::material::{Isotropic, Material};
+use crate::texture::Texture;
+
+use super::hitable::{HitRecord, Hitable};
+use super::ray::Ray;
+use super::rng::mix64;
+use nalgebra::Vector3;
+
+/// 密度均匀的参与介质（烟、雾）
+/// boundary：介质的边界，必须是凸的
+/// neg_inv_density：-1/密度
+/// phase_function：介质内的散射材质
+pub struct ConstantMedium<T: Hitable> {
+    boundary: T,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl<T: Hitable> ConstantMedium<T> {
+    /// density：密度，越大越浓；albedo：散射的衰减率
+    pub fn new(boundary: T, density: f64, albedo: impl Texture + 'static) -> ConstantMedium<T> {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Arc::new(Isotropic::new(albedo)),
+        }
+    }
+}
+
+/// 介质使用的[0, 1)内的随机数
+/// 光线携带的采样值按进入边界的参数entry做Cranley–Patterson旋转：
+/// 旋转后仍是均匀分布，而同一条光线穿过的几个介质入口不同，得到的随机数也不同
+fn medium_sample(r: &Ray, entry: f64) -> f64 {
+    let shift = (mix64(entry.to_bits()) >> 11) as f64 / (1u64 << 53) as f64;
+    (r.sample() + shift).fract()
+}
+
+impl<T: Hitable> Hitable for ConstantMedium<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // 找到光线进入与离开边界的位置，光线起点在介质内时也成立
+        let rec1 = self.boundary.hit(r, f64::NEG_INFINITY, f64::INFINITY)?;
+        let rec2 = self.boundary.hit(r, rec1.time() + 0.0001, f64::INFINITY)?;
+
+        let t1 = rec1.time().max(t_min).max(0.0);
+        let t2 = rec2.time().min(t_max);
+        if t1 >= t2 {
+            return None;
+        }
+
+        // 按指数分布随机决定光线在介质中散射的距离，1 - u落在(0, 1]内，对数有限
+        let ray_length = r.direction().norm();
+        let distance_inside_boundary = (t2 - t1) * ray_length;
+        let u = medium_sample(r, rec1.time());
+        let hit_distance = self.neg_inv_density * (1.0 - u).ln();
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let t = t1 + hit_distance / ray_length;
+        // 介质内的法线没有意义，任取一个
+        Some(HitRecord::new(
+            r.at(t),
+            Vector3::new(1.0, 0.0, 0.0),
+            t,
+            self.phase_function.clone(),
+            0.0,
+            0.0,
+        ))
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.boundary.bounding_box(time0, time1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+
+    fn fog(center: Vector3<f64>) -> ConstantMedium<Sphere> {
+        let white = SolidColor::new(Vector3::new(1.0, 1.0, 1.0));
+        let boundary = Sphere::new(center, 10.0, crate::material::Isotropic::new(white));
+        ConstantMedium::new(boundary, 1.0, SolidColor::new(Vector3::new(0.5, 0.5, 0.5)))
+    }
+
+    /// 沿+z的光线在介质中走过的距离
+    fn scatter_distance(medium: &ConstantMedium<Sphere>, sample: f64) -> f64 {
+        let ray = Ray::new(Vector3::zeros(), Vector3::z(), 0.0, sample);
+        let entry = medium
+            .boundary
+            .hit(&ray, 0.0, f64::INFINITY)
+            .unwrap()
+            .time();
+        medium.hit(&ray, 0.001, f64::MAX).unwrap().time() - entry
+    }
+
+    #[test]
+    fn scatter_distance_follows_the_ray_sample() {
+        let medium = fog(Vector3::new(0.0, 0.0, 20.0));
+        assert_eq!(
+            scatter_distance(&medium, 0.3),
+            scatter_distance(&medium, 0.3)
+        );
+        assert_ne!(
+            scatter_distance(&medium, 0.3),
+            scatter_distance(&medium, 0.7)
+        );
+    }
+
+    #[test]
+    fn media_along_one_path_use_different_samples() {
+        // 两团相同的雾前后排在同一条光线上，如果共用一个随机数，走过的距离会完全相同
+        let near = fog(Vector3::new(0.0, 0.0, 20.0));
+        let far = fog(Vector3::new(0.0, 0.0, 50.0));
+        for sample in [0.1, 0.5, 0.9] {
+            assert_ne!(
+                scatter_distance(&near, sample),
+                scatter_distance(&far, sample)
+            );
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHError, BVH};
+use crate::triangle::{interpolate_uv, intersect, shading_normal, triangle_bounding_box};
+
+use super::hitable::{HitRecord, Hitable};
+use super::material::Material;
+use super::ray::Ray;
+use nalgebra::Vector3;
+
+/// 网格的顶点缓冲，由所有三角形共享
+#[derive(Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub uvs: Vec<(f64, f64)>,
+    pub colors: Vec<Vector3<f64>>,
+}
+
+/// 网格中的一个三角面
+/// positions/normals/uvs：三个角在对应缓冲中的下标，可以各不相同
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub colors: Option<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+/// 引用共享缓冲的三角形，只作为网格内部BVH的叶子
+struct MeshTriangle {
+    buffers: Arc<MeshBuffers>,
+    face: MeshFace,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vector3<f64>; 3] {
+        let p = &self.buffers.positions;
+        let [i0, i1, i2] = self.face.positions;
+        [p[i0], p[i1], p[i2]]
+    }
+}
+
+impl Hitable for MeshTriangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let vertices = self.vertices();
+        let (t, b1, b2) = intersect(&vertices, r, t_min, t_max)?;
+        let geometric = (vertices[1] - vertices[0])
+            .cross(&(vertices[2] - vertices[0]))
+            .normalize();
+        let normal = match self.face.normals {
+            Some([i0, i1, i2]) => {
+                let n = &self.buffers.normals;
+                shading_normal(&[n[i0], n[i1], n[i2]], b1, b2, geometric)
+            }
+            None => geometric,
+        };
+        let (u, v) = match self.face.uvs {
+            Some([i0, i1, i2]) => {
+                let uv = &self.buffers.uvs;
+                interpolate_uv(&[uv[i0], uv[i1], uv[i2]], b1, b2)
+            }
+            None => (b1, b2),
+        };
+        let rec = HitRecord::new(r.at(t), normal, t, self.face.material.clone(), u, v);
+        Some(match self.face.colors {
+            Some([i0, i1, i2]) => {
+                let c = &self.buffers.colors;
+                rec.with_vertex_color((1.0 - b1 - b2) * c[i0] + b1 * c[i1] + b2 * c[i2])
+            }
+            None => rec,
+        })
+    }
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(triangle_bounding_box(&self.vertices()))
+    }
+}
+
+/// 三角网格，顶点缓冲共享，内部用BVH加速
+pub struct TriangleMesh {
+    bvh: BVH,
+}
+
+impl TriangleMesh {
+    /// faces中的下标必须在buffers的范围内
+    /// 顶点坐标含有NaN时返回错误，错误中的下标即面的序号
+    pub fn new(buffers: MeshBuffers, faces: Vec<MeshFace>) -> Result<TriangleMesh, BVHError> {
+        let buffers = Arc::new(buffers);
+        let triangles: Vec<Box<dyn Hitable>> = faces
+            .into_iter()
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    buffers: buffers.clone(),
+                    face,
+                }) as Box<dyn Hitable>
+            })
+            .collect();
+        Ok(TriangleMesh {
+            bvh: BVH::new(triangles, 0.0, 1.0)?,
+        })
+    }
+}
+
+impl Hitable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.bvh.bounding_box(time0, time1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+    use crate::transform::Translate;
+
+    #[test]
+    fn vertex_colors_follow_transformed_mesh() {
+        let buffers = MeshBuffers {
+            positions: vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ],
+            colors: vec![Vector3::x(), Vector3::y(), Vector3::z()],
+            ..MeshBuffers::default()
+        };
+        let face = MeshFace {
+            positions: [0, 1, 2],
+            normals: None,
+            uvs: None,
+            colors: Some([0, 1, 2]),
+            material: Arc::new(Lambertian::new(SolidColor::new(Vector3::zeros()))),
+        };
+        let mesh = TriangleMesh::new(buffers, vec![face]).unwrap();
+        let offset = Vector3::new(5.0, -3.0, 2.0);
+        let moved = Translate::new(mesh, offset);
+
+        let target = Vector3::new(0.25, 0.5, 0.0);
+        let origin = target + offset + Vector3::new(0.0, 0.0, 1.0);
+        let ray = Ray::new(origin, Vector3::new(0.0, 0.0, -1.0), 0.0, 0.5);
+        let color = moved
+            .hit(&ray, 0.001, f64::MAX)
+            .unwrap()
+            .vertex_color()
+            .unwrap();
+        assert!(
+            (color - Vector3::new(0.25, 0.25, 0.5)).norm() < 1e-9,
+            "{}",
+            color
+        );
+    }
+}
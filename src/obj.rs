@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::bvh::BVHError;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::mesh::{MeshBuffers, MeshFace, TriangleMesh};
+use crate::texture::{ImageTexture, SolidColor};
+use nalgebra::Vector3;
+
+/// OBJ/MTL加载错误
+#[derive(Debug)]
+pub enum ObjError {
+    /// 文件无法读取
+    Io { path: PathBuf, source: io::Error },
+    /// 文件内容有误，line从1开始
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// 文件中没有任何面
+    Empty { path: PathBuf },
+    /// 顶点数据无法构建BVH
+    Bvh { path: PathBuf, source: BVHError },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ObjError::Empty { path } => write!(f, "{}: no faces in mesh", path.display()),
+            ObjError::Bvh { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Bvh { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// 逐行读取文件，去掉注释与空行
+/// 输出：(行号, 关键字, 其余参数)
+fn statements(text: &str) -> impl Iterator<Item = (usize, &str, Vec<&str>)> {
+    text.lines().enumerate().filter_map(|(i, line)| {
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next()?;
+        Some((i + 1, keyword, tokens.collect()))
+    })
+}
+
+/// 解析至少min个、至多max个浮点数
+fn parse_floats(args: &[&str], min: usize, max: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min {
+        return Err(format!(
+            "expected at least {} numbers, found {}",
+            min,
+            args.len()
+        ));
+    }
+    args.iter()
+        .take(max)
+        .map(|a| {
+            a.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", a))
+        })
+        .collect()
+}
+
+fn parse_vector(args: &[&str]) -> Result<Vector3<f64>, String> {
+    let v = parse_floats(args, 3, 3)?;
+    Ok(Vector3::new(v[0], v[1], v[2]))
+}
+
+/// MTL中的材质描述
+struct MtlMaterial {
+    kd: Vector3<f64>,
+    ks: Vector3<f64>,
+    ke: Vector3<f64>,
+    ni: Option<f64>,
+    ns: Option<f64>,
+    dissolve: f64,
+    illum: u32,
+    map_kd: Option<ImageTexture>,
+}
+
+impl MtlMaterial {
+    fn new() -> MtlMaterial {
+        MtlMaterial {
+            kd: Vector3::new(0.8, 0.8, 0.8),
+            ks: Vector3::new(0.0, 0.0, 0.0),
+            ke: Vector3::new(0.0, 0.0, 0.0),
+            ni: None,
+            ns: None,
+            dissolve: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+
+    /// 转换为渲染器的材质，优先级：
+    /// 1. Ke非零：DiffuseLight
+    /// 2. 透明（d<1或illum为4/6/7）：Dielectric，折射率为Ni，缺省1.5
+    /// 3. Ks强于Kd：Metal，Ns越大越光滑
+    /// 4. 其他：Lambertian，有map_Kd时使用图像纹理
+    fn build(self) -> Arc<dyn Material> {
+        if self.ke.max() > 0.0 {
+            Arc::new(DiffuseLight::new(SolidColor::new(self.ke)))
+        } else if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7) {
+            Arc::new(Dielectric::new(self.ni.unwrap_or(1.5)))
+        } else if self.ks.max() > self.kd.max() {
+            let fuzz = self
+                .ns
+                .map_or(0.0, |ns| (1.0 - ns / 1000.0).clamp(0.0, 1.0));
+            Arc::new(Metal::new(self.ks, fuzz))
+        } else if let Some(texture) = self.map_kd {
+            Arc::new(Lambertian::new(texture))
+        } else {
+            Arc::new(Lambertian::new(SolidColor::new(self.kd)))
+        }
+    }
+}
+
+/// 读取MTL文件，返回材质名到材质的映射
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let text = read_file(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (line, keyword, args) in statements(&text) {
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(error("newmtl without a name".to_string()));
+            }
+            parsed.push((args.join(" "), MtlMaterial::new()));
+            continue;
+        }
+        let current = match parsed.last_mut() {
+            Some((_, m)) => m,
+            None => return Err(error(format!("'{}' before any newmtl", keyword))),
+        };
+        match keyword {
+            "Kd" => current.kd = parse_vector(&args).map_err(error)?,
+            "Ks" => current.ks = parse_vector(&args).map_err(error)?,
+            "Ke" => current.ke = parse_vector(&args).map_err(error)?,
+            "Ni" => {
+                let ni = parse_floats(&args, 1, 1).map_err(error)?[0];
+                if !(ni > 0.0 && ni.is_finite()) {
+                    return Err(error(format!(
+                        "Ni must be a positive finite number, got {}",
+                        ni
+                    )));
+                }
+                current.ni = Some(ni);
+            }
+            "Ns" => current.ns = Some(parse_floats(&args, 1, 1).map_err(error)?[0]),
+            "d" => current.dissolve = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "Tr" => current.dissolve = 1.0 - parse_floats(&args, 1, 1).map_err(error)?[0],
+            "illum" => {
+                current.illum = args
+                    .first()
+                    .and_then(|a| a.parse().ok())
+                    .ok_or_else(|| error("illum expects an integer".to_string()))?
+            }
+            "map_Kd" => {
+                // 文件名是最后一个参数，前面可能有-s、-o等选项，暂不支持
+                let file = args
+                    .last()
+                    .ok_or_else(|| error("map_Kd without a file name".to_string()))?;
+                let texture =
+                    ImageTexture::open(directory.join(file)).map_err(|e| error(e.to_string()))?;
+                current.map_kd = Some(texture);
+            }
+            // 其余语句（Ka等）暂不支持，忽略
+            _ => {}
+        }
+    }
+
+    Ok(parsed
+        .into_iter()
+        .map(|(name, m)| (name, m.build()))
+        .collect())
+}
+
+/// 把OBJ中从1开始、可为负数的下标转换为从0开始的下标
+fn resolve_index(token: &str, count: usize) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid index '{}'", token))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "index {} out of range (have {} elements)",
+            index, count
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// 面的一个角：v、v/vt、v//vn或v/vt/vn
+fn parse_corner(
+    token: &str,
+    buffers: &MeshBuffers,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), buffers.positions.len())?;
+    let uv = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(t, buffers.uvs.len())?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(t, buffers.normals.len())?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex '{}'", token));
+    }
+    Ok((position, uv, normal))
+}
+
+/// 读取OBJ文件，构建三角网格
+/// 多边形面按扇形拆分为三角形，材质由mtllib/usemtl指定
+pub fn load_obj(path: impl AsRef<Path>) -> Result<TriangleMesh, ObjError> {
+    let path = path.as_ref();
+    let text = read_file(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::new(
+        Vector3::new(0.73, 0.73, 0.73),
+    )));
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut current_material = default_material;
+
+    let mut buffers = MeshBuffers::default();
+    let mut faces: Vec<MeshFace> = Vec::new();
+
+    for (line, keyword, args) in statements(&text) {
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        match keyword {
+            "v" => buffers.positions.push(parse_vector(&args).map_err(error)?),
+            "vn" => buffers.normals.push(parse_vector(&args).map_err(error)?),
+            "vt" => {
+                let uv = parse_floats(&args, 1, 2).map_err(error)?;
+                buffers.uvs.push((uv[0], uv.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        args.len()
+                    )));
+                }
+                let corners = args
+                    .iter()
+                    .map(|a| parse_corner(a, &buffers))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                // 只有所有角都给出时才使用uv/法线
+                let has_uv = corners.iter().all(|c| c.1.is_some());
+                let has_normal = corners.iter().all(|c| c.2.is_some());
+                for i in 1..corners.len() - 1 {
+                    let tri = [corners[0], corners[i], corners[i + 1]];
+                    faces.push(MeshFace {
+                        positions: tri.map(|c| c.0),
+                        uvs: has_uv.then(|| tri.map(|c| c.1.unwrap())),
+                        normals: has_normal.then(|| tri.map(|c| c.2.unwrap())),
+                        colors: None,
+                        material: current_material.clone(),
+                    });
+                }
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(error("mtllib without a file name".to_string()));
+                }
+                // 每个参数是一个独立的材质库，同名材质以后读到的为准
+                for name in &args {
+                    materials.extend(load_mtl(&directory.join(name))?);
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                current_material = materials
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| error(format!("unknown material '{}'", name)))?;
+            }
+            // o、g、s、l等语句与渲染无关，忽略
+            _ => {}
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(ObjError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+    TriangleMesh::new(buffers, faces).map_err(|source| ObjError::Bvh {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在临时目录中写入若干文件，返回目录
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "restart_raytrace_obj_{}_{}",
+            test,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, text) in files {
+            std::fs::write(directory.join(name), text).unwrap();
+        }
+        directory
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    #[test]
+    fn mtllib_loads_every_library() {
+        let obj = format!(
+            "mtllib a.mtl b.mtl\n{}usemtl from_a\nf 1 2 3\nusemtl from_b\nf 1 2 3\n",
+            TRIANGLE
+        );
+        let directory = write_files(
+            "mtllib",
+            &[
+                ("a.mtl", "newmtl from_a\nKd 1 0 0\n"),
+                ("b.mtl", "newmtl from_b\nKd 0 1 0\n"),
+                ("mesh.obj", &obj),
+            ],
+        );
+        let mesh = load_obj(directory.join("mesh.obj"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(mesh.is_ok(), "{}", mesh.err().unwrap());
+    }
+
+    #[test]
+    fn invalid_ni_is_rejected() {
+        for ni in ["0", "-1.5", "inf", "NaN"] {
+            let mtl = format!("newmtl glass\nd 0.5\nNi {}\n", ni);
+            let obj = format!("mtllib glass.mtl\n{}usemtl glass\nf 1 2 3\n", TRIANGLE);
+            let directory = write_files("ni", &[("glass.mtl", &mtl), ("mesh.obj", &obj)]);
+            let result = load_obj(directory.join("mesh.obj"));
+            std::fs::remove_dir_all(&directory).unwrap();
+            match result {
+                Err(ObjError::Parse { path, line, .. }) => {
+                    assert!(path.ends_with("glass.mtl"));
+                    assert_eq!(line, 3);
+                }
+                Err(e) => panic!("Ni {}: unexpected error {}", ni, e),
+                Ok(_) => panic!("Ni {} was accepted", ni),
+            }
+        }
+    }
+}
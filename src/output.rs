@@ -0,0 +1,231 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+
+use crate::exr::{write_exr, ExrCompression};
+use crate::framebuffer::Image;
+use crate::tonemap::{ToneMapError, ToneMapping};
+
+/// 输出图像格式
+/// PPM与PNG为8位；PFM与EXR保存线性浮点值，保留HDR信息
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// 二进制PPM (P6)
+    Ppm,
+    Png,
+    Pfm,
+    Exr,
+}
+
+impl ImageFormat {
+    /// 按扩展名判断格式，不区分大小写
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "pfm" => Some(ImageFormat::Pfm),
+            "exr" => Some(ImageFormat::Exr),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Png => "png",
+            ImageFormat::Pfm => "pfm",
+            ImageFormat::Exr => "exr",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ppm" => Ok(ImageFormat::Ppm),
+            "png" => Ok(ImageFormat::Png),
+            "pfm" => Ok(ImageFormat::Pfm),
+            "exr" => Ok(ImageFormat::Exr),
+            _ => Err(format!(
+                "unknown image format '{}' (expected ppm, png, pfm or exr)",
+                s
+            )),
+        }
+    }
+}
+
+/// 图像输出错误
+#[derive(Debug)]
+pub enum OutputError {
+    /// 未指定格式，且无法从扩展名判断
+    UnknownFormat { path: PathBuf },
+    /// 文件无法创建或写入
+    Io { path: PathBuf, source: io::Error },
+    /// PNG编码失败
+    Encode {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    /// 色调映射参数不合法
+    ToneMapping { source: ToneMapError },
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::UnknownFormat { path } => write!(
+                f,
+                "{}: cannot tell the image format from the extension (use .ppm, .png, .pfm or .exr)",
+                path.display()
+            ),
+            OutputError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            OutputError::Encode { path, source } => write!(f, "{}: {}", path.display(), source),
+            OutputError::ToneMapping { source } => source.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutputError::UnknownFormat { .. } => None,
+            OutputError::Io { source, .. } => Some(source),
+            OutputError::Encode { source, .. } => Some(source),
+            OutputError::ToneMapping { source } => Some(source),
+        }
+    }
+}
+
+/// 写入二进制PPM (P6)
+/// pixels：从上到下、从左到右的RGB
+fn write_ppm(out: &mut dyn Write, width: usize, height: usize, pixels: &[u8]) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    out.write_all(pixels)
+}
+
+/// 写入PNG
+fn write_png(
+    out: &mut dyn Write,
+    width: usize,
+    height: usize,
+    pixels: &[u8],
+) -> Result<(), image::ImageError> {
+    PngEncoder::new(out).write_image(pixels, width as u32, height as u32, ExtendedColorType::Rgb8)
+}
+
+/// 写入PFM，像素为小端32位浮点，按规范从最下面一行开始
+fn write_pfm(out: &mut dyn Write, image: &Image) -> io::Result<()> {
+    write!(out, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    for y in (0..image.height()).rev() {
+        for pixel in image.row(y) {
+            for c in pixel {
+                out.write_all(&c.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 确定输出格式：format为None时按扩展名判断，标准输出缺省为PPM
+/// 应在渲染前调用，尽早发现无法识别的扩展名
+pub fn output_format(path: &Path, format: Option<ImageFormat>) -> Result<ImageFormat, OutputError> {
+    match format {
+        Some(format) => Ok(format),
+        None if path == Path::new("-") => Ok(ImageFormat::Ppm),
+        None => ImageFormat::from_path(path).ok_or_else(|| OutputError::UnknownFormat {
+            path: path.to_path_buf(),
+        }),
+    }
+}
+
+/// 把图像写入path，path为'-'时写入标准输出
+/// tone_mapping只对8位格式有效，PFM与EXR保存原始的线性值；exr_compression只对EXR有效
+/// tone_mapping不合法时不创建文件，直接返回错误
+pub fn write_image(
+    path: &Path,
+    format: ImageFormat,
+    image: &Image,
+    tone_mapping: &ToneMapping,
+    exr_compression: ExrCompression,
+) -> Result<(), OutputError> {
+    let io_error = |source| OutputError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let tone_mapping_error = |source| OutputError::ToneMapping { source };
+    tone_mapping.validate().map_err(tone_mapping_error)?;
+
+    let mut out: Box<dyn Write> = if path == Path::new("-") {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(File::create(path).map_err(io_error)?))
+    };
+    let (width, height) = (image.width(), image.height());
+    match format {
+        ImageFormat::Ppm => {
+            let pixels = tone_mapping.to_rgb8(image).map_err(tone_mapping_error)?;
+            write_ppm(out.as_mut(), width, height, &pixels).map_err(io_error)?
+        }
+        ImageFormat::Png => {
+            let pixels = tone_mapping.to_rgb8(image).map_err(tone_mapping_error)?;
+            write_png(out.as_mut(), width, height, &pixels).map_err(|source| {
+                OutputError::Encode {
+                    path: path.to_path_buf(),
+                    source,
+                }
+            })?
+        }
+        ImageFormat::Pfm => write_pfm(out.as_mut(), image).map_err(io_error)?,
+        ImageFormat::Exr => write_exr(out.as_mut(), image, exr_compression).map_err(io_error)?,
+    }
+    out.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pfm_rows_are_stored_bottom_up_in_little_endian() {
+        let (width, height) = (3, 2);
+        let pixels = (0..width * height)
+            .map(|i| [i as f32, -(i as f32), 0.5 + i as f32])
+            .collect();
+        let image = Image::from_pixels(width, height, pixels);
+        let mut bytes = Vec::new();
+        write_pfm(&mut bytes, &image).unwrap();
+
+        // 头部是三行文本：类型、尺寸、比例（负数表示小端）
+        let mut lines = bytes.splitn(4, |&b| b == b'\n');
+        assert_eq!(lines.next().unwrap(), b"PF");
+        assert_eq!(lines.next().unwrap(), b"3 2");
+        let scale: f32 = std::str::from_utf8(lines.next().unwrap())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(scale < 0.0);
+
+        let data = lines.next().unwrap();
+        assert_eq!(data.len(), width * height * 12);
+        let floats: Vec<f32> = data
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        for (file_row, pixels) in floats.chunks(width * 3).enumerate() {
+            let expected: Vec<f32> = image.row(height - 1 - file_row).concat();
+            assert_eq!(pixels, expected.as_slice(), "row {} of the file", file_row);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use nalgebra::Vector3;
 use rand::Rng;
 
-// point_count=256
+use crate::rng::RenderRng;
+
+/// Perlin梯度噪声，point_count=256
 pub struct Perlin {
     ranfloat: Vec<Vector3<f64>>,
     perm_x: Vec<usize>,
@@ -10,8 +12,8 @@ pub struct Perlin {
 }
 
 impl Perlin {
-    pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
+    /// 梯度与置换表都由rng生成，相同的序列得到相同的噪声
+    pub fn new(rng: &mut RenderRng) -> Self {
         let mut ranfloat = Vec::with_capacity(256);
         for _ in 0..256 {
             ranfloat.push(
@@ -23,9 +25,9 @@ impl Perlin {
                 .normalize(),
             );
         }
-        let perm_x = Self::perlin_generate_perm();
-        let perm_y = Self::perlin_generate_perm();
-        let perm_z = Self::perlin_generate_perm();
+        let perm_x = Self::perlin_generate_perm(rng);
+        let perm_y = Self::perlin_generate_perm(rng);
+        let perm_z = Self::perlin_generate_perm(rng);
         Self {
             ranfloat,
             perm_x,
@@ -34,6 +36,7 @@ impl Perlin {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn noise(&self, p: &Vector3<f64>) -> f64 {
         let u = p.x - f64::floor(p.x);
         let v = p.y - f64::floor(p.y);
@@ -59,26 +62,25 @@ impl Perlin {
     }
 
     // 生成一个Vec<usize>
-    fn perlin_generate_perm() -> Vec<usize> {
+    fn perlin_generate_perm(rng: &mut RenderRng) -> Vec<usize> {
         let mut p = Vec::with_capacity(256);
         for i in 0..256 {
             p.push(i);
         }
-        Self::permute(&mut p, 256);
+        Self::permute(&mut p, 256, rng);
         p
     }
 
     // 交换，打乱P
     // 输入使用切片，可以防止改变大小
-    fn permute(p: &mut [usize], n: usize) {
-        let mut rng = rand::thread_rng();
+    fn permute(p: &mut [usize], n: usize, rng: &mut RenderRng) {
         for i in (0..n).rev() {
             let target = rng.gen_range(0..=i);
             p.swap(i, target);
         }
     }
 
-    #[allow(dead_code)]
+    #[allow(dead_code, clippy::needless_range_loop)]
     fn trilinear_interp(c: [[[f64; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
         let mut accum = 0.0;
         for i in 0..2 {
@@ -94,6 +96,7 @@ impl Perlin {
         accum
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn perlin_interp(c: [[[Vector3<f64>; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
         // Hermite cubic
         let uu = u * u * (3.0 - 2.0 * u);
@@ -116,15 +119,16 @@ impl Perlin {
         accum
     }
 
+    /// depth层噪声叠加的湍流，每层频率加倍、权重减半
     pub fn turb(&self, p: &Vector3<f64>, depth: usize) -> f64 {
         let mut accum = 0.0;
-        let mut temp_p = p.clone();
+        let mut temp_p = *p;
         let mut weight = 1.0;
 
-        for i in 0..depth {
-            accum += &weight * self.noise(&temp_p);
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
             weight *= 0.5;
-            temp_p = temp_p * 2.0;
+            temp_p *= 2.0;
         }
 
         accum.abs()
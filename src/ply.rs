@@ -0,0 +1,507 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::bvh::BVHError;
+use crate::material::{Lambertian, Material};
+use crate::mesh::{MeshBuffers, MeshFace, TriangleMesh};
+use crate::texture::{SolidColor, VertexColorTexture};
+use nalgebra::Vector3;
+
+/// PLY加载错误
+#[derive(Debug)]
+pub enum PlyError {
+    /// 文件无法读取
+    Io { path: PathBuf, source: io::Error },
+    /// 文件头有误，line从1开始
+    Header {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// 数据部分有误
+    Data { path: PathBuf, message: String },
+    /// 文件中没有任何面
+    Empty { path: PathBuf },
+    /// 顶点数据无法构建BVH
+    Bvh { path: PathBuf, source: BVHError },
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            PlyError::Header {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            PlyError::Data { path, message } => write!(f, "{}: {}", path.display(), message),
+            PlyError::Empty { path } => write!(f, "{}: no faces in mesh", path.display()),
+            PlyError::Bvh { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlyError::Io { source, .. } => Some(source),
+            PlyError::Bvh { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+/// PLY的标量类型
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// 整数类型的颜色范围是0..255，浮点类型是0..1
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::F32 | Scalar::F64 => 1.0,
+            _ => 1.0 / 255.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar {
+        name: String,
+        ty: Scalar,
+    },
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// 数据部分的读取器，ASCII与二进制统一读为f64
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary(&'a [u8]),
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens.next().ok_or("unexpected end of file")?;
+                token
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number '{}'", token))
+            }
+            Body::Binary(bytes) => {
+                if bytes.len() < ty.size() {
+                    return Err("unexpected end of file".to_string());
+                }
+                let (head, rest) = bytes.split_at(ty.size());
+                *bytes = rest;
+                let value = match ty {
+                    Scalar::I8 => head[0] as i8 as f64,
+                    Scalar::U8 => head[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([head[0], head[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([head[0], head[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes(head.try_into().unwrap()) as f64,
+                    Scalar::U32 => u32::from_le_bytes(head.try_into().unwrap()) as f64,
+                    Scalar::F32 => f32::from_le_bytes(head.try_into().unwrap()) as f64,
+                    Scalar::F64 => f64::from_le_bytes(head.try_into().unwrap()),
+                };
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// 列表长度与顶点下标必须是非负整数
+/// 直接as usize会把负数与NaN截成0、把小数截尾，损坏的面就悄悄读进来了
+fn to_index(value: f64, what: &str) -> Result<usize, String> {
+    if value >= 0.0 && value.fract() == 0.0 {
+        Ok(value as usize)
+    } else {
+        Err(format!("invalid {} {}", what, value))
+    }
+}
+
+/// 解析文件头，返回格式、元素列表与数据部分的起始字节
+fn parse_header(path: &Path, bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), PlyError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+
+    loop {
+        line_number += 1;
+        let error = |message: String| PlyError::Header {
+            path: path.to_path_buf(),
+            line: line_number,
+            message,
+        };
+        let end = bytes[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| error("missing end_header".to_string()))?;
+        let line = std::str::from_utf8(&bytes[offset..offset + end])
+            .map_err(|_| error("header is not valid text".to_string()))?
+            .trim();
+        offset += end + 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line_number == 1 {
+            if line != "ply" {
+                return Err(error("not a PLY file".to_string()));
+            }
+            continue;
+        }
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, ..] => return Err(error(format!("unsupported format '{}'", other))),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(format!("invalid element count '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let count = Scalar::parse(count)
+                    .ok_or_else(|| error(format!("unknown type '{}'", count)))?;
+                let item =
+                    Scalar::parse(item).ok_or_else(|| error(format!("unknown type '{}'", item)))?;
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element".to_string()))?
+                    .properties
+                    .push(Property::List {
+                        name: name.to_string(),
+                        count,
+                        item,
+                    });
+            }
+            ["property", ty, name] => {
+                let ty =
+                    Scalar::parse(ty).ok_or_else(|| error(format!("unknown type '{}'", ty)))?;
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element".to_string()))?
+                    .properties
+                    .push(Property::Scalar {
+                        name: name.to_string(),
+                        ty,
+                    });
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(error(format!("malformed header line '{}'", line))),
+        }
+    }
+
+    let format = format.ok_or_else(|| PlyError::Header {
+        path: path.to_path_buf(),
+        line: line_number,
+        message: "missing format line".to_string(),
+    })?;
+    Ok((format, elements, offset))
+}
+
+/// 读取PLY文件（ASCII或二进制小端），构建三角网格
+/// 支持顶点位置、法线、uv与顶点颜色；多边形面按扇形拆分为三角形
+/// 有顶点颜色时，所有面共用一个以VertexColorTexture为纹理的Lambertian
+pub fn load_ply(path: impl AsRef<Path>) -> Result<TriangleMesh, PlyError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|source| PlyError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let (format, elements, offset) = parse_header(path, &bytes)?;
+    let data_error = |message: String| PlyError::Data {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let mut body = match format {
+        Format::Ascii => Body::Ascii(
+            std::str::from_utf8(&bytes[offset..])
+                .map_err(|_| data_error("ASCII body is not valid text".to_string()))?
+                .split_ascii_whitespace(),
+        ),
+        Format::BinaryLittleEndian => Body::Binary(&bytes[offset..]),
+    };
+
+    let mut buffers = MeshBuffers::default();
+    let mut polygons: Vec<Vec<usize>> = Vec::new();
+
+    for element in &elements {
+        for index in 0..element.count {
+            let at =
+                |message: String| data_error(format!("{} #{}: {}", element.name, index, message));
+            // 当前元素的标量属性与列表属性
+            let mut position = Vector3::new(0.0, 0.0, 0.0);
+            let mut normal = Vector3::new(0.0, 0.0, 0.0);
+            let mut uv = (0.0, 0.0);
+            let mut color = Vector3::new(0.0, 0.0, 0.0);
+            let mut indices: Vec<usize> = Vec::new();
+
+            for property in &element.properties {
+                match property {
+                    Property::Scalar { name, ty } => {
+                        let value = body.read(*ty).map_err(at)?;
+                        match name.as_str() {
+                            "x" => position.x = value,
+                            "y" => position.y = value,
+                            "z" => position.z = value,
+                            "nx" => normal.x = value,
+                            "ny" => normal.y = value,
+                            "nz" => normal.z = value,
+                            "u" | "s" | "texture_u" | "texture_s" => uv.0 = value,
+                            "v" | "t" | "texture_v" | "texture_t" => uv.1 = value,
+                            "red" | "diffuse_red" => color.x = value * ty.color_scale(),
+                            "green" | "diffuse_green" => color.y = value * ty.color_scale(),
+                            "blue" | "diffuse_blue" => color.z = value * ty.color_scale(),
+                            _ => {}
+                        }
+                    }
+                    Property::List { name, count, item } => {
+                        let n = body.read(*count).map_err(at)?;
+                        let n = to_index(n, "list count").map_err(at)?;
+                        for _ in 0..n {
+                            let value = body.read(*item).map_err(at)?;
+                            if name == "vertex_indices" || name == "vertex_index" {
+                                indices.push(to_index(value, "vertex index").map_err(at)?);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    buffers.positions.push(position);
+                    buffers.normals.push(normal);
+                    buffers.uvs.push(uv);
+                    buffers.colors.push(color);
+                }
+                "face" => {
+                    if indices.len() < 3 {
+                        return Err(at(format!(
+                            "face needs at least 3 vertices, found {}",
+                            indices.len()
+                        )));
+                    }
+                    polygons.push(indices);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 根据文件头决定使用哪些顶点属性
+    let vertex = elements.iter().find(|e| e.name == "vertex");
+    let has = |names: &[&str]| {
+        vertex.is_some_and(|e| {
+            e.properties.iter().any(
+                |p| matches!(p, Property::Scalar { name, .. } if names.contains(&name.as_str())),
+            )
+        })
+    };
+    let has_normals = has(&["nx"]);
+    let has_uvs = has(&["u", "s", "texture_u", "texture_s"]);
+    let has_colors = has(&["red", "diffuse_red"]);
+
+    let gray = Vector3::new(0.73, 0.73, 0.73);
+    let material: Arc<dyn Material> = if has_colors {
+        Arc::new(Lambertian::new(VertexColorTexture::new(gray)))
+    } else {
+        Arc::new(Lambertian::new(SolidColor::new(gray)))
+    };
+    let vertex_count = buffers.positions.len();
+    let mut faces: Vec<MeshFace> = Vec::new();
+    for (index, polygon) in polygons.iter().enumerate() {
+        if let Some(&bad) = polygon.iter().find(|&&i| i >= vertex_count) {
+            return Err(data_error(format!(
+                "face #{}: vertex index {} out of range (have {} vertices)",
+                index, bad, vertex_count
+            )));
+        }
+        for i in 1..polygon.len() - 1 {
+            let tri = [polygon[0], polygon[i], polygon[i + 1]];
+            faces.push(MeshFace {
+                positions: tri,
+                normals: has_normals.then_some(tri),
+                uvs: has_uvs.then_some(tri),
+                colors: has_colors.then_some(tri),
+                material: material.clone(),
+            });
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(PlyError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+    TriangleMesh::new(buffers, faces).map_err(|source| PlyError::Bvh {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hitable::Hitable;
+    use crate::ray::Ray;
+
+    /// 把文件写到临时目录后加载
+    fn load(test: &str, bytes: &[u8]) -> Result<TriangleMesh, PlyError> {
+        let path = std::env::temp_dir().join(format!(
+            "restart_raytrace_ply_{}_{}.ply",
+            test,
+            std::process::id()
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        let mesh = load_ply(&path);
+        std::fs::remove_file(&path).unwrap();
+        mesh
+    }
+
+    const HEADER: &str = "element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float u
+property float v
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    /// 位置、法线、uv、颜色
+    type Vertex = ([f32; 3], [f32; 3], [f32; 2], [u8; 3]);
+
+    const VERTICES: [Vertex; 3] = [
+        ([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0], [255, 0, 0]),
+        ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0], [0, 255, 0]),
+        ([0.0, 1.0, 0.0], [0.0, 1.0, 0.0], [0.0, 1.0], [0, 0, 255]),
+    ];
+
+    /// 在重心坐标(0.25, 0.25, 0.5)处命中，检查各属性的插值
+    fn check_triangle(mesh: &TriangleMesh) {
+        let ray = Ray::new(
+            Vector3::new(0.25, 0.5, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            0.0,
+            0.5,
+        );
+        let rec = mesh.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!((rec.point() - Vector3::new(0.25, 0.5, 0.0)).norm() < 1e-6);
+        let normal = Vector3::new(0.0, 1.0, 1.0).normalize();
+        assert!((rec.normal() - normal).norm() < 1e-6, "{}", rec.normal());
+        assert!((rec.u() - 0.5).abs() < 1e-6 && (rec.v() - 0.75).abs() < 1e-6);
+        let color = rec.vertex_color().unwrap();
+        assert!(
+            (color - Vector3::new(0.25, 0.25, 0.5)).norm() < 1e-6,
+            "{}",
+            color
+        );
+    }
+
+    #[test]
+    fn ascii_attributes_are_interpolated() {
+        let mut text = format!("ply\nformat ascii 1.0\n{}", HEADER);
+        for (p, n, uv, c) in VERTICES {
+            text += &format!(
+                "{} {} {} {} {} {} {} {} {} {} {}\n",
+                p[0], p[1], p[2], n[0], n[1], n[2], uv[0], uv[1], c[0], c[1], c[2]
+            );
+        }
+        text += "3 0 1 2\n";
+        check_triangle(&load("ascii", text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_little_endian_attributes_are_interpolated() {
+        let mut bytes = format!("ply\nformat binary_little_endian 1.0\n{}", HEADER).into_bytes();
+        for (p, n, uv, c) in VERTICES {
+            for value in p.iter().chain(&n).chain(&uv) {
+                bytes.extend(value.to_le_bytes());
+            }
+            bytes.extend(c);
+        }
+        bytes.push(3);
+        for index in [0i32, 1, 2] {
+            bytes.extend(index.to_le_bytes());
+        }
+        check_triangle(&load("binary", &bytes).unwrap());
+    }
+
+    #[test]
+    fn invalid_indices_are_rejected() {
+        let header = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 2\nproperty list int int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        for face in ["3 0 1 -1", "3 0 1.5 2", "3 0 nan 2", "-3 0 1 2", "3 0 1 3"] {
+            let text = format!("{}{}\n", header, face);
+            match load("index", text.as_bytes()) {
+                Err(e @ PlyError::Data { .. }) => {
+                    assert!(e.to_string().contains("face #1"), "{}: {}", face, e)
+                }
+                Err(e) => panic!("{}: unexpected error {}", face, e),
+                Ok(_) => panic!("{}: corrupt face was accepted", face),
+            }
+        }
+    }
+}
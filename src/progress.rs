@@ -0,0 +1,105 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::render::TileProgress;
+
+/// 在标准错误上报告渲染进度：完成百分比、每秒光线数与剩余时间
+/// 终端上原地刷新一行；否则为避免刷屏，每10%输出一行
+pub struct ProgressReporter {
+    start: Instant,
+    interactive: bool,
+    /// 上次输出的时间与百分比
+    last: Mutex<(Instant, usize)>,
+}
+
+impl Default for ProgressReporter {
+    fn default() -> Self {
+        ProgressReporter::new()
+    }
+}
+
+impl ProgressReporter {
+    /// 终端上两次刷新的最小间隔
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// 从现在开始计时
+    pub fn new() -> ProgressReporter {
+        let start = Instant::now();
+        ProgressReporter {
+            start,
+            interactive: io::stderr().is_terminal(),
+            last: Mutex::new((start, 0)),
+        }
+    }
+
+    /// 作为Renderer的块回调，每完成一块调用一次
+    pub fn update(&self, progress: &TileProgress) {
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        let fraction = progress.pixels_done as f64 / progress.pixels_total as f64;
+        let percent = (fraction * 100.0) as usize;
+        let finished = progress.tiles_done == progress.tiles_total;
+
+        let mut last = self.last.lock().unwrap();
+        let due = if self.interactive {
+            now - last.0 >= Self::INTERVAL
+        } else {
+            percent / 10 > last.1 / 10
+        };
+        if !(due || finished) {
+            return;
+        }
+        *last = (now, percent);
+
+        let rays_per_second = progress.rays as f64 / elapsed.as_secs_f64().max(1e-9);
+        let line = if finished {
+            format!(
+                "100% | {} rays/s | done in {}",
+                si(rays_per_second),
+                clock(elapsed)
+            )
+        } else {
+            let eta = elapsed.mul_f64((1.0 - fraction) / fraction.max(1e-9));
+            format!(
+                "{:3}% | {} rays/s | ETA {}",
+                percent,
+                si(rays_per_second),
+                clock(eta)
+            )
+        };
+        // 进度只是提示，写失败时忽略
+        let mut stderr = io::stderr().lock();
+        let _ = if self.interactive {
+            let end = if finished { "\n" } else { "" };
+            write!(stderr, "\r\x1b[K{}{}", line, end)
+        } else {
+            writeln!(stderr, "{}", line)
+        };
+        let _ = stderr.flush();
+    }
+}
+
+/// 以k、M、G为单位的数值
+fn si(x: f64) -> String {
+    if x >= 1e9 {
+        format!("{:.2}G", x / 1e9)
+    } else if x >= 1e6 {
+        format!("{:.2}M", x / 1e6)
+    } else if x >= 1e3 {
+        format!("{:.2}k", x / 1e3)
+    } else {
+        format!("{:.0}", x)
+    }
+}
+
+/// 时长，格式为h:mm:ss或m:ss
+fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
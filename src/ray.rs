@@ -1,20 +1,36 @@
 use nalgebra::Vector3;
 
+/// 光线：origin + t * direction，time为光线发出的时刻
 pub struct Ray {
     origin: Vector3<f64>,
     direction: Vector3<f64>,
     time: f64,
+    sample: f64,
 }
 
 impl Ray {
-    pub fn new(origin: Vector3<f64>, direction: Vector3<f64>, time: f64) -> Ray {
+    /// direction不必是单位向量
+    /// sample：[0, 1)内的随机数，由发出光线的一方从采样器中取得，
+    /// 供求交时需要随机决策的物体（如参与介质）使用
+    pub fn new(origin: Vector3<f64>, direction: Vector3<f64>, time: f64, sample: f64) -> Ray {
         Ray {
             origin,
             direction,
             time,
+            sample,
         }
     }
 
+    /// 起点与方向换成另一坐标系中的值，时刻与随机数不变
+    pub fn moved(&self, origin: Vector3<f64>, direction: Vector3<f64>) -> Ray {
+        Ray {
+            origin,
+            direction,
+            ..*self
+        }
+    }
+
+    /// 参数t处的点
     pub fn at(&self, t: f64) -> Vector3<f64> {
         self.origin + self.direction * t
     }
@@ -28,4 +44,7 @@ impl Ray {
     pub fn time(&self) -> f64 {
         self.time
     }
+    pub fn sample(&self) -> f64 {
+        self.sample
+    }
 }
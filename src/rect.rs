@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+
+use super::material::Material;
+
+use super::hitable::{HitRecord, Hitable};
+use super::ray::Ray;
+use nalgebra::Vector3;
+
+/// 包围盒在矩形法线方向上的厚度，防止包围盒退化为平面
+const PAD: f64 = 0.0001;
+
+/// 位于z=k平面上的矩形
+/// x0..x1, y0..y1：矩形范围
+pub struct XYRect {
+    x0: f64,
+    x1: f64,
+    y0: f64,
+    y1: f64,
+    k: f64,
+    material: Arc<dyn Material>,
+}
+
+impl XYRect {
+    /// 法线朝向z轴正方向
+    pub fn new(
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+        material: impl Material + 'static,
+    ) -> XYRect {
+        XYRect::with_material(x0, x1, y0, y1, k, Arc::new(material))
+    }
+
+    /// 与其他物体共享同一个材质
+    pub fn with_material(
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+        material: Arc<dyn Material>,
+    ) -> XYRect {
+        XYRect {
+            x0,
+            x1,
+            y0,
+            y1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hitable for XYRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin().z) / r.direction().z;
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let x = r.origin().x + t * r.direction().x;
+        let y = r.origin().y + t * r.direction().y;
+        if x < self.x0 || x > self.x1 || y < self.y0 || y > self.y1 {
+            return None;
+        }
+        let u = (x - self.x0) / (self.x1 - self.x0);
+        let v = (y - self.y0) / (self.y1 - self.y0);
+        Some(HitRecord::new(
+            r.at(t),
+            Vector3::new(0.0, 0.0, 1.0),
+            t,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+    /// 返回z方向加厚后的包围盒
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(AABB::new(
+            Vector3::new(self.x0, self.y0, self.k - PAD),
+            Vector3::new(self.x1, self.y1, self.k + PAD),
+        ))
+    }
+}
+
+/// 位于y=k平面上的矩形
+/// x0..x1, z0..z1：矩形范围
+pub struct XZRect {
+    x0: f64,
+    x1: f64,
+    z0: f64,
+    z1: f64,
+    k: f64,
+    material: Arc<dyn Material>,
+}
+
+impl XZRect {
+    /// 法线朝向y轴正方向
+    pub fn new(
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: impl Material + 'static,
+    ) -> XZRect {
+        XZRect::with_material(x0, x1, z0, z1, k, Arc::new(material))
+    }
+
+    /// 与其他物体共享同一个材质
+    pub fn with_material(
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: Arc<dyn Material>,
+    ) -> XZRect {
+        XZRect {
+            x0,
+            x1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hitable for XZRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin().y) / r.direction().y;
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let x = r.origin().x + t * r.direction().x;
+        let z = r.origin().z + t * r.direction().z;
+        if x < self.x0 || x > self.x1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let u = (x - self.x0) / (self.x1 - self.x0);
+        let v = (z - self.z0) / (self.z1 - self.z0);
+        Some(HitRecord::new(
+            r.at(t),
+            Vector3::new(0.0, 1.0, 0.0),
+            t,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+    /// 返回y方向加厚后的包围盒
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(AABB::new(
+            Vector3::new(self.x0, self.k - PAD, self.z0),
+            Vector3::new(self.x1, self.k + PAD, self.z1),
+        ))
+    }
+}
+
+/// 位于x=k平面上的矩形
+/// y0..y1, z0..z1：矩形范围
+pub struct YZRect {
+    y0: f64,
+    y1: f64,
+    z0: f64,
+    z1: f64,
+    k: f64,
+    material: Arc<dyn Material>,
+}
+
+impl YZRect {
+    /// 法线朝向x轴正方向
+    pub fn new(
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: impl Material + 'static,
+    ) -> YZRect {
+        YZRect::with_material(y0, y1, z0, z1, k, Arc::new(material))
+    }
+
+    /// 与其他物体共享同一个材质
+    pub fn with_material(
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: Arc<dyn Material>,
+    ) -> YZRect {
+        YZRect {
+            y0,
+            y1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hitable for YZRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin().x) / r.direction().x;
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let y = r.origin().y + t * r.direction().y;
+        let z = r.origin().z + t * r.direction().z;
+        if y < self.y0 || y > self.y1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let u = (y - self.y0) / (self.y1 - self.y0);
+        let v = (z - self.z0) / (self.z1 - self.z0);
+        Some(HitRecord::new(
+            r.at(t),
+            Vector3::new(1.0, 0.0, 0.0),
+            t,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+    /// 返回x方向加厚后的包围盒
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(AABB::new(
+            Vector3::new(self.k - PAD, self.y0, self.z0),
+            Vector3::new(self.k + PAD, self.y1, self.z1),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    fn gray() -> Lambertian {
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5)))
+    }
+
+    fn assert_uv(rec: HitRecord, u: f64, v: f64) {
+        assert!(
+            (rec.u() - u).abs() < 1e-12 && (rec.v() - v).abs() < 1e-12,
+            "({}, {})",
+            rec.u(),
+            rec.v()
+        );
+    }
+
+    #[test]
+    fn hit_uv_is_relative_to_the_rect_extent() {
+        // 每个矩形都在第一个轴的1/4处、第二个轴的3/4处被击中
+        let xy = XYRect::new(1.0, 3.0, -2.0, 2.0, 5.0, gray());
+        let ray = Ray::new(Vector3::new(1.5, 1.0, 0.0), Vector3::z(), 0.0, 0.5);
+        let rec = xy.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!((rec.time() - 5.0).abs() < 1e-12);
+        assert_eq!(rec.normal(), Vector3::z());
+        assert_uv(rec, 0.25, 0.75);
+
+        let xz = XZRect::new(0.0, 4.0, 0.0, 2.0, -1.0, gray());
+        let ray = Ray::new(Vector3::new(1.0, 0.0, 1.5), -Vector3::y(), 0.0, 0.5);
+        assert_uv(xz.hit(&ray, 0.001, f64::MAX).unwrap(), 0.25, 0.75);
+
+        let yz = YZRect::new(-1.0, 1.0, 2.0, 6.0, 2.0, gray());
+        let ray = Ray::new(Vector3::new(0.0, -0.5, 5.0), Vector3::x(), 0.0, 0.5);
+        assert_uv(yz.hit(&ray, 0.001, f64::MAX).unwrap(), 0.25, 0.75);
+
+        // 范围之外
+        let ray = Ray::new(Vector3::new(3.5, 0.0, 0.0), Vector3::z(), 0.0, 0.5);
+        assert!(xy.hit(&ray, 0.001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn shared_material_is_not_wrapped_again() {
+        let material: Arc<dyn Material> = Arc::new(gray());
+        let rect = XYRect::with_material(0.0, 1.0, 0.0, 1.0, 0.0, material.clone());
+        let ray = Ray::new(Vector3::new(0.5, 0.5, 1.0), -Vector3::z(), 0.0, 0.5);
+        let rec = rect.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!(Arc::ptr_eq(&rec.material(), &material));
+    }
+}
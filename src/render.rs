@@ -0,0 +1,325 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use nalgebra::Vector3;
+use rayon::prelude::*;
+
+use crate::background::Background;
+use crate::film::{Film, FilmTile};
+use crate::filter::FilterKind;
+use crate::framebuffer::Image;
+use crate::hitable::Hitable;
+use crate::ray::Ray;
+use crate::sampler::{Sampler, SamplerKind};
+use crate::scene::{ImageSettings, Scene};
+use crate::tile::{tiles, Tile, TileOrder};
+
+/// 渲染参数
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    pub image: ImageSettings,
+    /// 同一种子总是渲染出同样的图像
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub filter: FilterKind,
+    /// 滤波器半径，单位为像素
+    pub filter_radius: f64,
+    /// 块的边长，单位为像素
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        let filter = FilterKind::Gaussian;
+        RenderSettings {
+            image: ImageSettings::default(),
+            seed: 0,
+            sampler: SamplerKind::Sobol,
+            filter,
+            filter_radius: filter.default_radius(),
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// 检查参数能否用于渲染，render会先调用它
+    pub fn validate(&self) -> Result<(), RenderError> {
+        let (width, height) = (self.image.width, self.image.height());
+        if width == 0 || height == 0 {
+            return Err(RenderError::EmptyImage { width, height });
+        }
+        if self.image.samples_per_pixel == 0 {
+            return Err(RenderError::NoSamples);
+        }
+        if !(self.filter_radius > 0.0 && self.filter_radius.is_finite()) {
+            return Err(RenderError::InvalidFilterRadius {
+                radius: self.filter_radius,
+            });
+        }
+        if self.tile_size == 0 {
+            return Err(RenderError::ZeroTileSize);
+        }
+        Ok(())
+    }
+}
+
+/// 不合法的渲染参数
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// 宽或高为0，通常是宽高比相对宽度太大
+    EmptyImage {
+        width: usize,
+        height: usize,
+    },
+    /// 每像素采样数为0
+    NoSamples,
+    /// 滤波器半径不是正的有限值
+    InvalidFilterRadius {
+        radius: f64,
+    },
+    ZeroTileSize,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::EmptyImage { width, height } => write!(
+                f,
+                "image is {}x{} pixels; the aspect ratio may be too large for the width",
+                width, height
+            ),
+            RenderError::NoSamples => f.write_str("samples per pixel must be > 0"),
+            RenderError::InvalidFilterRadius { radius } => {
+                write!(f, "filter radius must be > 0, got {}", radius)
+            }
+            RenderError::ZeroTileSize => f.write_str("tile size must be > 0"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// 一块渲染完成时传给回调的信息，计数均为到目前为止的累计值
+pub struct TileProgress<'a> {
+    pub tile: &'a Tile,
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub pixels_done: usize,
+    pub pixels_total: usize,
+    /// 已追踪的光线数，包括散射产生的光线
+    pub rays: u64,
+}
+
+type TileCallback<'a> = Box<dyn Fn(&TileProgress) + Send + Sync + 'a>;
+
+/// 渲染场景，等价于不注册回调的Renderer
+pub fn render(scene: &Scene, settings: &RenderSettings) -> Result<Image, RenderError> {
+    Renderer::new(scene, settings).render()
+}
+
+/// 分块渲染器：把图像切成块，用rayon并行渲染
+/// 每块的采样先累加到自己的胶片块，最后按块的序号合并，结果与线程数和调度顺序无关
+pub struct Renderer<'a> {
+    scene: &'a Scene,
+    settings: &'a RenderSettings,
+    callbacks: Vec<TileCallback<'a>>,
+}
+
+impl<'a> Renderer<'a> {
+    /// 按settings渲染scene
+    pub fn new(scene: &'a Scene, settings: &'a RenderSettings) -> Renderer<'a> {
+        Renderer {
+            scene,
+            settings,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// 注册一个块完成时的回调
+    /// 回调在渲染线程上按完成的先后调用，可能同时被多个线程调用
+    pub fn on_tile(mut self, callback: impl Fn(&TileProgress) + Send + Sync + 'a) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// 渲染整幅图像，阻塞直到所有块完成；参数不合法时不渲染，直接返回错误
+    pub fn render(&self) -> Result<Image, RenderError> {
+        let settings = self.settings;
+        settings.validate()?;
+        let width = settings.image.width;
+        let height = settings.image.height();
+        let mut film = Film::new(
+            width,
+            height,
+            settings.filter.create(settings.filter_radius),
+        );
+
+        let tiles = tiles(width, height, settings.tile_size, settings.tile_order);
+        let tiles_done = AtomicUsize::new(0);
+        let pixels_done = AtomicUsize::new(0);
+        let rays = AtomicU64::new(0);
+        let film_tiles: Vec<FilmTile> = tiles
+            .par_iter()
+            .map(|tile| {
+                let mut film_tile = film.tile(tile.xs.clone(), tile.ys.clone());
+                let tile_rays = self.render_tile(tile, &mut film_tile);
+
+                // 先累加像素与光线，最后一块完成时它们已是总数
+                let progress = TileProgress {
+                    tile,
+                    rays: rays.fetch_add(tile_rays, Ordering::SeqCst) + tile_rays,
+                    pixels_done: pixels_done.fetch_add(tile.pixel_count(), Ordering::SeqCst)
+                        + tile.pixel_count(),
+                    pixels_total: width * height,
+                    tiles_done: tiles_done.fetch_add(1, Ordering::SeqCst) + 1,
+                    tiles_total: tiles.len(),
+                };
+                for callback in &self.callbacks {
+                    callback(&progress);
+                }
+                film_tile
+            })
+            .collect();
+
+        for film_tile in film_tiles {
+            film.merge_tile(film_tile);
+        }
+        Ok(film.into_image())
+    }
+
+    /// 渲染一块中的所有像素，返回追踪的光线数
+    fn render_tile(&self, tile: &Tile, film_tile: &mut FilmTile) -> u64 {
+        let scene = self.scene;
+        let image = &self.settings.image;
+        let (width, height) = (image.width as f64, image.height() as f64);
+        let mut sampler = self
+            .settings
+            .sampler
+            .create(self.settings.seed, image.samples_per_pixel);
+        let mut rays = 0;
+        for image_y in tile.ys.clone() {
+            for image_x in tile.xs.clone() {
+                for sample in 0..image.samples_per_pixel {
+                    // 每个采样的维度只由种子、像素与采样序号决定，与线程数和调度顺序无关
+                    sampler.start_sample(image_x, image_y, sample);
+                    let (dx, dy) = sampler.get_2d();
+                    let (x, y) = (image_x as f64 + dx, image_y as f64 + dy);
+                    // 光栅坐标y向下，viewport坐标v向上
+                    let (u, v) = (x / width, 1.0 - y / height);
+
+                    let color = ray_color(
+                        scene.camera.get_ray(u, v, sampler.as_mut()),
+                        &scene.world,
+                        scene.background.as_ref(),
+                        image.max_depth,
+                        sampler.as_mut(),
+                        &mut rays,
+                    );
+                    film_tile.add_sample(x, y, color);
+                }
+            }
+        }
+        rays
+    }
+}
+
+/// 沿光线r收集的辐射，rays累加追踪的光线数
+fn ray_color(
+    r: Ray,
+    world: &dyn Hitable,
+    background: &dyn Background,
+    depth: usize,
+    sampler: &mut dyn Sampler,
+    rays: &mut u64,
+) -> Vector3<f64> {
+    if depth == 0 {
+        return Vector3::new(0.0, 0.0, 0.0);
+    };
+    *rays += 1;
+    if let Some(rec) = world.hit(&r, 0.001, f64::MAX) {
+        // 自发光 + 散射光
+        let emitted = rec.material().emitted(rec.u(), rec.v(), rec.point());
+        if let Some((sactter, albedo)) = rec.material().scatter(&r, &rec, sampler) {
+            return emitted
+                + albedo.component_mul(&ray_color(
+                    sactter,
+                    world,
+                    background,
+                    depth - 1,
+                    sampler,
+                    rays,
+                ));
+        }
+        emitted
+    } else {
+        background.value(&r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::RenderRng;
+    use crate::scenes;
+
+    fn small_settings() -> RenderSettings {
+        let mut settings = RenderSettings::default();
+        settings.image.width = 16;
+        settings.image.aspect_ratio = 1.0;
+        settings.image.samples_per_pixel = 2;
+        settings.image.max_depth = 4;
+        settings
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let settings = small_settings();
+        let scene =
+            scenes::find("cornell-box").unwrap()(1.0, &mut RenderRng::from_seed(0)).unwrap();
+        let with = |change: fn(&mut RenderSettings)| {
+            let mut invalid = settings.clone();
+            change(&mut invalid);
+            render(&scene, &invalid).err()
+        };
+        assert_eq!(with(|s| s.tile_size = 0), Some(RenderError::ZeroTileSize));
+        assert_eq!(
+            with(|s| s.image.samples_per_pixel = 0),
+            Some(RenderError::NoSamples)
+        );
+        assert_eq!(
+            with(|s| s.filter_radius = -1.0),
+            Some(RenderError::InvalidFilterRadius { radius: -1.0 })
+        );
+        assert_eq!(
+            with(|s| s.image.aspect_ratio = 100.0),
+            Some(RenderError::EmptyImage {
+                width: 16,
+                height: 0
+            })
+        );
+        assert!(render(&scene, &settings).is_ok());
+    }
+
+    #[test]
+    fn thread_count_does_not_change_the_image() {
+        // 烟雾场景覆盖介质的散射距离，小块保证多个线程都分到工作
+        let mut settings = small_settings();
+        settings.tile_size = 4;
+        let scene =
+            scenes::find("cornell-smoke").unwrap()(1.0, &mut RenderRng::from_seed(0)).unwrap();
+        let render_with = |threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| render(&scene, &settings).unwrap())
+        };
+        let serial = render_with(1);
+        let parallel = render_with(4);
+        assert_eq!(serial.pixels(), parallel.pixels());
+        assert!(serial.pixels().iter().flatten().any(|c| *c > 0.0));
+    }
+}
@@ -0,0 +1,79 @@
+use rand::{Error, RngCore};
+
+/// SplitMix64的混合函数，把相近的输入打散为不相关的64位值
+pub fn mix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// 渲染用的确定性随机数发生器（PCG32 XSH-RR）
+/// 相同的种子总是产生相同的序列，与线程数和调度顺序无关
+#[derive(Clone)]
+pub struct RenderRng {
+    state: u64,
+    inc: u64,
+}
+
+impl RenderRng {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+    /// seed：初始状态，stream：序列编号，不同编号的序列互不相关
+    pub fn new(seed: u64, stream: u64) -> RenderRng {
+        let mut rng = RenderRng {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    /// 由全局种子派生的场景构建用序列
+    pub fn from_seed(seed: u64) -> RenderRng {
+        RenderRng::new(mix64(seed), 0)
+    }
+
+    /// 像素(x, y)的第sample个采样使用的序列
+    pub fn for_sample(seed: u64, x: usize, y: usize, sample: usize) -> RenderRng {
+        let key = mix64(seed ^ mix64(x as u64 ^ mix64(y as u64 ^ mix64(sample as u64))));
+        RenderRng::new(key, mix64(key))
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(self.inc);
+    }
+}
+
+impl RngCore for RenderRng {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let high = self.next_u32() as u64;
+        let low = self.next_u32() as u64;
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,529 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::rng::{mix64, RenderRng};
+
+/// 采样器，为一个像素的每个采样依次提供[0, 1)内的采样维度
+/// 每次调用get_1d/get_2d消耗下一个维度，同一采样内维度的使用顺序应当固定
+pub trait Sampler {
+    /// 开始像素(x, y)的第index个采样，维度从0重新计数
+    fn start_sample(&mut self, x: usize, y: usize, index: usize);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// 采样器种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    /// seed：全局随机种子，samples_per_pixel：每个像素的采样数
+    pub fn create(self, seed: u64, samples_per_pixel: usize) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+impl fmt::Display for SamplerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(SamplerKind::Independent),
+            "stratified" => Ok(SamplerKind::Stratified),
+            "halton" => Ok(SamplerKind::Halton),
+            "sobol" => Ok(SamplerKind::Sobol),
+            _ => Err(format!(
+                "unknown sampler '{}' (expected independent, stratified, halton or sobol)",
+                s
+            )),
+        }
+    }
+}
+
+/// 把若干个值混合为一个哈希
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, v| mix64(h ^ v))
+}
+
+/// 把32位整数映射到[0, 1)
+fn to_unit(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
+
+/// 把64位哈希映射到[0, 1)
+fn hash_to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 由哈希p决定的[0, l)的一个排列中的第i个元素（Kensler, Correlated Multi-Jittered Sampling）
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            return (i.wrapping_add(p)) % l;
+        }
+    }
+}
+
+/// 像素(x, y)的哈希，用来让不同像素的采样互不相关
+fn pixel_hash(seed: u64, x: usize, y: usize) -> u64 {
+    hash(&[seed, x as u64, y as u64])
+}
+
+/// 独立均匀随机采样
+pub struct IndependentSampler {
+    seed: u64,
+    rng: RenderRng,
+}
+
+impl IndependentSampler {
+    /// seed：全局随机种子
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler {
+            seed,
+            rng: RenderRng::for_sample(seed, 0, 0, 0),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.rng = RenderRng::for_sample(self.seed, x, y, index);
+    }
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen()
+    }
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
+
+/// 分层抖动采样
+/// 每个维度把[0, 1)分为samples_per_pixel层（二维时为近似正方形的网格），
+/// 每个采样落在随机排列后的一层中，层内再随机抖动
+pub struct StratifiedSampler {
+    seed: u64,
+    x_strata: u32,
+    y_strata: u32,
+    pixel: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    /// samples_per_pixel：每个像素的采样数，决定分层的格数
+    pub fn new(seed: u64, samples_per_pixel: usize) -> StratifiedSampler {
+        let x_strata = ((samples_per_pixel as f64).sqrt().round() as u32).max(1);
+        let y_strata = (samples_per_pixel as u32).div_ceil(x_strata).max(1);
+        StratifiedSampler {
+            seed,
+            x_strata,
+            y_strata,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// 当前维度的排列种子与抖动哈希
+    fn next_dimension(&mut self) -> (u32, u64) {
+        let dimension_hash = hash(&[self.pixel, self.dimension]);
+        self.dimension += 1;
+        (
+            dimension_hash as u32,
+            hash(&[dimension_hash, self.index as u64]),
+        )
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_hash(self.seed, x, y);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+    fn get_1d(&mut self) -> f64 {
+        let strata = self.x_strata * self.y_strata;
+        let (permutation, jitter) = self.next_dimension();
+        let stratum = permutation_element(self.index % strata, strata, permutation);
+        ((stratum as f64 + hash_to_unit(jitter)) / strata as f64).min(ONE_MINUS_EPSILON)
+    }
+    fn get_2d(&mut self) -> (f64, f64) {
+        let strata = self.x_strata * self.y_strata;
+        let (permutation, jitter) = self.next_dimension();
+        let stratum = permutation_element(self.index % strata, strata, permutation);
+        let (sx, sy) = (stratum % self.x_strata, stratum / self.x_strata);
+        let dx = hash_to_unit(jitter);
+        let dy = hash_to_unit(mix64(jitter));
+        (
+            ((sx as f64 + dx) / self.x_strata as f64).min(ONE_MINUS_EPSILON),
+            ((sy as f64 + dy) / self.y_strata as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+/// 小于1的最大f64
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Halton序列各维度使用的底数
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// 以base为底的根式反演，每一位数字按哈希做Owen置乱
+fn owen_scrambled_radical_inverse(base: u64, mut a: u64, seed: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+    // 精度用尽前一直处理数字，a为0后的高位数字同样需要置乱
+    while 1.0 - (base - 1) as f64 * inv_base_m < 1.0 {
+        let next = a / base;
+        let digit = a - next * base;
+        // 置乱只依赖于更低位的数字，保证是嵌套置乱
+        let digit_hash = mix64(seed ^ reversed_digits) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_hash) as u64;
+        // 较大的底数在精度用尽前base^n就会超出u64，此时剩余的数字已不影响结果
+        match reversed_digits
+            .checked_mul(base)
+            .and_then(|r| r.checked_add(digit))
+        {
+            Some(r) => reversed_digits = r,
+            None => break,
+        }
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (inv_base_m * reversed_digits as f64).min(ONE_MINUS_EPSILON)
+}
+
+/// Owen置乱的Halton序列
+/// 所有像素共用同一序列，各像素的置乱不同；维度超过PRIMES时退化为随机采样
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: u64,
+    index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    /// seed决定每个维度的Owen扰乱
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn sample_dimension(&self, dimension: usize) -> f64 {
+        let seed = hash(&[self.pixel, dimension as u64]);
+        match PRIMES.get(dimension) {
+            Some(&base) => owen_scrambled_radical_inverse(base, self.index, seed),
+            None => hash_to_unit(hash(&[seed, self.index])),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_hash(self.seed, x, y);
+        self.index = index as u64;
+        self.dimension = 0;
+    }
+    fn get_1d(&mut self) -> f64 {
+        let value = self.sample_dimension(self.dimension);
+        self.dimension += 1;
+        value
+    }
+    fn get_2d(&mut self) -> (f64, f64) {
+        let value = (
+            self.sample_dimension(self.dimension),
+            self.sample_dimension(self.dimension + 1),
+        );
+        self.dimension += 2;
+        value
+    }
+}
+
+/// 由Joe–Kuo参数(s, a, m)生成Sobol序列一个维度的32个方向数
+const fn sobol_directions(s: usize, a: u32, m: [u32; 3]) -> [u32; 32] {
+    let mut v = [0u32; 32];
+    let mut i = 0;
+    while i < 32 {
+        if i < s {
+            v[i] = m[i] << (31 - i);
+        } else {
+            let mut x = v[i - s] ^ (v[i - s] >> s);
+            let mut k = 1;
+            while k < s {
+                x ^= ((a >> (s - 1 - k)) & 1) * v[i - k];
+                k += 1;
+            }
+            v[i] = x;
+        }
+        i += 1;
+    }
+    v
+}
+
+/// Sobol序列第1到3维的方向数，第0维为van der Corput序列，不需要方向数
+const SOBOL_DIRECTIONS: [[u32; 32]; 3] = [
+    sobol_directions(1, 0, [1, 0, 0]),
+    sobol_directions(2, 1, [1, 3, 0]),
+    sobol_directions(3, 1, [1, 3, 1]),
+];
+
+fn sobol(index: u32, dimension: usize) -> u32 {
+    // 第0维的方向数为1 << (31 - i)，即逐位取反
+    if dimension == 0 {
+        return index.reverse_bits();
+    }
+    let mut x = 0;
+    let mut bits = index;
+    let mut i = 0;
+    while bits != 0 {
+        if bits & 1 != 0 {
+            x ^= SOBOL_DIRECTIONS[dimension - 1][i];
+        }
+        bits >>= 1;
+        i += 1;
+    }
+    x
+}
+
+/// Laine–Karras哈希，作用于逆序后的整数，只让低位影响高位
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+/// 基于哈希的嵌套均匀置乱（Owen置乱）
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Owen置乱的Sobol序列（Burley, Practical Hash-based Owen Scrambling）
+/// 每4个维度为一组，组内使用Sobol的前4维；各组的采样序号独立打乱，使组间互不相关
+pub struct SobolSampler {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: usize,
+}
+
+impl SobolSampler {
+    /// seed决定每个维度的Owen扰乱
+    pub fn new(seed: u64) -> SobolSampler {
+        SobolSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn sample_dimension(&self, dimension: usize) -> f64 {
+        let group_seed = hash(&[self.pixel, (dimension / 4) as u64]);
+        let index = nested_uniform_scramble(self.index, group_seed as u32);
+        let d = dimension % 4;
+        let x = sobol(index, d);
+        to_unit(nested_uniform_scramble(
+            x,
+            hash(&[group_seed, d as u64]) as u32,
+        ))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = pixel_hash(self.seed, x, y);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+    fn get_1d(&mut self) -> f64 {
+        let value = self.sample_dimension(self.dimension);
+        self.dimension += 1;
+        value
+    }
+    fn get_2d(&mut self) -> (f64, f64) {
+        // 二维采样不跨组，保证两个维度来自同一组Sobol点
+        if self.dimension % 4 == 3 {
+            self.dimension += 1;
+        }
+        let value = (
+            self.sample_dimension(self.dimension),
+            self.sample_dimension(self.dimension + 1),
+        );
+        self.dimension += 2;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverse_stays_in_unit_interval_for_every_base() {
+        for &base in &PRIMES {
+            for a in [0, 1, base - 1, 1 << 20, u32::MAX as u64, u64::MAX] {
+                let value = owen_scrambled_radical_inverse(base, a, 0x1234_5678);
+                assert!(
+                    (0.0..1.0).contains(&value),
+                    "base {} a {}: {}",
+                    base,
+                    a,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_dimension_in_unit_interval_for_large_indices() {
+        for kind in [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            let mut sampler = kind.create(7, 64);
+            for index in [0, 1, 63, 1 << 16, (1 << 31) - 1, u32::MAX as usize] {
+                sampler.start_sample(3, 5, index);
+                for _ in 0..PRIMES.len() + 4 {
+                    let x = sampler.get_1d();
+                    let (u, v) = sampler.get_2d();
+                    for value in [x, u, v] {
+                        assert!(
+                            (0.0..1.0).contains(&value),
+                            "{} sampler, index {}: {}",
+                            kind,
+                            index,
+                            value
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// 每个像素用n个采样估计∫∫uv dudv = 1/4，返回多个像素上的均方误差
+    /// skip：先跳过的二维维度数，检查后面的维度同样分布良好
+    fn integral_error(kind: SamplerKind, n: usize, skip: usize) -> f64 {
+        let mut sampler = kind.create(11, n);
+        let pixels = 64;
+        let mut squared_error = 0.0;
+        for pixel in 0..pixels {
+            let mut sum = 0.0;
+            for index in 0..n {
+                sampler.start_sample(pixel % 8, pixel / 8, index);
+                for _ in 0..skip {
+                    sampler.get_2d();
+                }
+                let (u, v) = sampler.get_2d();
+                sum += u * v;
+            }
+            squared_error += (sum / n as f64 - 0.25).powi(2);
+        }
+        squared_error / pixels as f64
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_have_lower_error_than_independent() {
+        // 前两个维度上误差可低一到三个数量级；Halton的后几个维度底数较大，64个采样时只好几倍
+        for skip in [0, 3] {
+            let independent = integral_error(SamplerKind::Independent, 64, skip);
+            for kind in [
+                SamplerKind::Stratified,
+                SamplerKind::Halton,
+                SamplerKind::Sobol,
+            ] {
+                let error = integral_error(kind, 64, skip);
+                assert!(
+                    error * 4.0 < independent,
+                    "{} sampler, skip {}: {:e} vs independent {:e}",
+                    kind,
+                    skip,
+                    error,
+                    independent
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_samples_occupy_every_stratum_once() {
+        // 16 = 4x4，12 = 3x4
+        for (n, x_strata, y_strata) in [(16, 4, 4), (12, 3, 4)] {
+            let mut sampler = SamplerKind::Stratified.create(5, n);
+            let mut strata_1d = vec![0; n];
+            let mut strata_2d = vec![0; n];
+            for index in 0..n {
+                sampler.start_sample(2, 9, index);
+                let x = sampler.get_1d();
+                let (u, v) = sampler.get_2d();
+                strata_1d[(x * n as f64) as usize] += 1;
+                let (sx, sy) = (
+                    (u * x_strata as f64) as usize,
+                    (v * y_strata as f64) as usize,
+                );
+                strata_2d[sy * x_strata + sx] += 1;
+            }
+            assert!(strata_1d.iter().all(|&c| c == 1), "{:?}", strata_1d);
+            assert!(strata_2d.iter().all(|&c| c == 1), "{:?}", strata_2d);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serde::Deserialize;
+
+use crate::background::Background;
+use crate::bvh::BVH;
+use crate::camera::Camera;
+
+/// 场景：物体、未命中时的背景与观察它的相机
+pub struct Scene {
+    /// 所有物体放在一个BVH中
+    pub world: BVH,
+    pub background: Box<dyn Background>,
+    pub camera: Camera,
+}
+
+/// 图像参数
+/// width：图像宽度，高度由宽高比决定
+/// max_depth：光线的最大弹射次数
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageSettings {
+    pub width: usize,
+    pub aspect_ratio: f64,
+    pub samples_per_pixel: usize,
+    pub max_depth: usize,
+}
+
+impl ImageSettings {
+    /// 按宽高比计算的图像高度，向下取整
+    pub fn height(&self) -> usize {
+        ((self.width as f64) / self.aspect_ratio) as usize
+    }
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        ImageSettings {
+            width: 600,
+            aspect_ratio: 3.0 / 2.0,
+            samples_per_pixel: 20,
+            max_depth: 5,
+        }
+    }
+}
+
+/// 覆盖图像参数，为None的项保持不变
+#[derive(Clone, Debug, Default)]
+pub struct ImageOverrides {
+    pub width: Option<usize>,
+    pub aspect_ratio: Option<f64>,
+    pub samples_per_pixel: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+impl ImageOverrides {
+    /// 把不为None的项写入image
+    pub fn apply(&self, image: &mut ImageSettings) {
+        if let Some(width) = self.width {
+            image.width = width;
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            image.aspect_ratio = aspect_ratio;
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            image.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(max_depth) = self.max_depth {
+            image.max_depth = max_depth;
+        }
+    }
+}
@@ -0,0 +1,1115 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nalgebra::{Matrix4, Vector3};
+use serde::Deserialize;
+
+use crate::background::{Background, GradientBackground, SolidBackground, TextureBackground};
+use crate::bvh::{BVHError, BVH};
+use crate::camera::Camera;
+use crate::cuboid::Cuboid;
+use crate::hitable::{FlipFace, Hitable};
+use crate::material::{Dielectric, DiffuseLight, Isotropic, Lambertian, Material, Metal};
+use crate::medium::ConstantMedium;
+use crate::obj::load_obj;
+use crate::ply::load_ply;
+use crate::rect::{XYRect, XZRect, YZRect};
+use crate::rng::RenderRng;
+use crate::scene::{ImageOverrides, ImageSettings, Scene};
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{
+    Axis, CheckerTexture, FilterMode, ImageTexture, MarbleTexture, NoiseTexture, SolidColor,
+    Texture, TurbulenceTexture, WoodTexture, WrapMode,
+};
+use crate::transform::{RotateX, RotateY, RotateZ, Transform, Translate};
+use crate::triangle::Triangle;
+
+/// 场景文件加载错误
+#[derive(Debug)]
+pub enum SceneError {
+    /// 文件无法读取
+    Io { path: PathBuf, source: io::Error },
+    /// 不是合法的TOML，或字段的类型不符
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// 内容不合法，message以出错字段的路径开头，如materials.glass.ior
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            SceneError::Parse { path, source } => write!(f, "{}: {}", path.display(), source),
+            SceneError::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Parse { source, .. } => Some(source),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+/// 加载后的场景文件：图像参数与场景
+pub struct SceneFile {
+    pub image: ImageSettings,
+    pub scene: Scene,
+}
+
+type V3 = [f64; 3];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    image: ImageSettings,
+    camera: CameraDesc,
+    #[serde(default)]
+    background: BackgroundDesc,
+    #[serde(default)]
+    textures: BTreeMap<String, TextureDesc>,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectDesc>,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+}
+
+/// focus_distance缺省为look_from到look_at的距离
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: V3,
+    look_at: V3,
+    #[serde(default = "default_up")]
+    up: V3,
+    vfov: f64,
+    #[serde(default)]
+    aperture: f64,
+    focus_distance: Option<f64>,
+    #[serde(default)]
+    time0: f64,
+    #[serde(default = "one")]
+    time1: f64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    Solid {
+        color: V3,
+    },
+    Gradient {
+        bottom: V3,
+        top: V3,
+    },
+    #[default]
+    Sky,
+    Texture {
+        texture: TextureRef,
+    },
+}
+
+/// 纹理引用：直接给出颜色，或textures中的名字
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextureRef {
+    Color(V3),
+    Name(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: V3,
+    },
+    Checker {
+        odd: TextureRef,
+        even: TextureRef,
+    },
+    Noise {
+        scale: f64,
+    },
+    Turbulence {
+        scale: f64,
+        #[serde(default = "default_octaves")]
+        octaves: usize,
+        color0: TextureRef,
+        color1: TextureRef,
+    },
+    Marble {
+        scale: f64,
+        #[serde(default = "default_octaves")]
+        octaves: usize,
+        axis: Axis,
+        color0: TextureRef,
+        color1: TextureRef,
+    },
+    Wood {
+        scale: f64,
+        #[serde(default = "default_octaves")]
+        octaves: usize,
+        axis: Axis,
+        color0: TextureRef,
+        color1: TextureRef,
+    },
+    /// file相对于场景文件所在的目录
+    Image {
+        file: PathBuf,
+        #[serde(default = "default_wrap")]
+        wrap: WrapMode,
+        #[serde(default = "default_filter")]
+        filter: FilterMode,
+        #[serde(default = "default_true")]
+        srgb: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: V3,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    Dielectric {
+        ior: f64,
+    },
+    DiffuseLight {
+        emit: TextureRef,
+    },
+    Isotropic {
+        albedo: TextureRef,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ShapeDesc {
+    Sphere {
+        center: V3,
+        radius: f64,
+    },
+    MovingSphere {
+        center0: V3,
+        center1: V3,
+        radius: f64,
+        #[serde(default)]
+        time0: f64,
+        #[serde(default = "one")]
+        time1: f64,
+    },
+    XyRect {
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+    },
+    XzRect {
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+    },
+    YzRect {
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+    },
+    Box {
+        min: V3,
+        max: V3,
+    },
+    Triangle {
+        vertices: [V3; 3],
+    },
+    /// OBJ或PLY网格，按扩展名区分，使用文件自带的材质
+    Mesh {
+        file: PathBuf,
+    },
+    /// 以boundary为边界、密度均匀的参与介质
+    Medium {
+        boundary: Box<ShapeDesc>,
+        density: f64,
+        albedo: TextureRef,
+    },
+}
+
+impl ShapeDesc {
+    /// 只需要一个材质的简单形状
+    fn is_primitive(&self) -> bool {
+        !matches!(self, ShapeDesc::Mesh { .. } | ShapeDesc::Medium { .. })
+    }
+}
+
+/// 依次作用于物体的变换
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDesc {
+    Translate(V3),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Scale(V3),
+}
+
+/// flip：翻转法线，在所有变换之前作用
+#[derive(Deserialize)]
+struct ObjectDesc {
+    #[serde(flatten)]
+    shape: ShapeDesc,
+    material: Option<String>,
+    #[serde(default)]
+    flip: bool,
+    #[serde(default)]
+    transforms: Vec<TransformDesc>,
+}
+
+/// 光源：发光颜色为emit的简单形状
+#[derive(Deserialize)]
+struct LightDesc {
+    #[serde(flatten)]
+    shape: ShapeDesc,
+    emit: V3,
+    #[serde(default)]
+    flip: bool,
+    #[serde(default)]
+    transforms: Vec<TransformDesc>,
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn default_up() -> V3 {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_octaves() -> usize {
+    7
+}
+
+fn default_wrap() -> WrapMode {
+    WrapMode::Repeat
+}
+
+fn default_filter() -> FilterMode {
+    FilterMode::Bilinear
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn vector(v: V3) -> Vector3<f64> {
+    Vector3::new(v[0], v[1], v[2])
+}
+
+fn positive(value: f64, path: &str) -> Result<f64, String> {
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("{} must be > 0", path))
+    }
+}
+
+fn non_negative(value: f64, path: &str) -> Result<f64, String> {
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("{} must be >= 0", path))
+    }
+}
+
+/// 检查hi > lo，错误信息指向hi字段
+fn interval(lo: f64, hi: f64, path: &str, lo_name: &str, hi_name: &str) -> Result<(), String> {
+    if lo < hi {
+        Ok(())
+    } else {
+        Err(format!("{}.{} must be > {}", path, hi_name, lo_name))
+    }
+}
+
+fn check_image(image: &ImageSettings) -> Result<(), String> {
+    if image.width == 0 {
+        return Err("image.width must be > 0".to_string());
+    }
+    positive(image.aspect_ratio, "image.aspect_ratio")?;
+    if image.height() == 0 {
+        return Err("image.aspect_ratio is too large for image.width".to_string());
+    }
+    if image.samples_per_pixel == 0 {
+        return Err("image.samples_per_pixel must be > 0".to_string());
+    }
+    if image.max_depth == 0 {
+        return Err("image.max_depth must be > 0".to_string());
+    }
+    Ok(())
+}
+
+fn build_camera(desc: &CameraDesc, aspect_ratio: f64) -> Result<Camera, String> {
+    let look_from = vector(desc.look_from);
+    let look_at = vector(desc.look_at);
+    let distance = (look_from - look_at).norm();
+    if distance == 0.0 {
+        return Err("camera.look_at must differ from camera.look_from".to_string());
+    }
+    if !(desc.vfov > 0.0 && desc.vfov < 180.0) {
+        return Err("camera.vfov must be in (0, 180)".to_string());
+    }
+    let up = vector(desc.up);
+    if up.cross(&(look_from - look_at)).norm() == 0.0 {
+        return Err("camera.up must not be parallel to the view direction".to_string());
+    }
+    let aperture = non_negative(desc.aperture, "camera.aperture")?;
+    let focus_distance = match desc.focus_distance {
+        Some(d) => positive(d, "camera.focus_distance")?,
+        None => distance,
+    };
+    interval(desc.time0, desc.time1, "camera", "time0", "time1")?;
+    Ok(Camera::new(
+        look_from,
+        look_at,
+        up,
+        desc.vfov,
+        aspect_ratio,
+        aperture,
+        focus_distance,
+        desc.time0,
+        desc.time1,
+    ))
+}
+
+/// 按名字构建纹理与材质，同名的只构建一次，之后通过Arc共享
+struct Builder<'a> {
+    directory: &'a Path,
+    texture_descs: &'a BTreeMap<String, TextureDesc>,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    /// 正在构建的纹理，用于发现循环引用
+    building: Vec<String>,
+    rng: &'a mut RenderRng,
+}
+
+impl Builder<'_> {
+    fn texture(&mut self, name: &str, path: &str) -> Result<Arc<dyn Texture>, String> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+        if self.building.iter().any(|n| n == name) {
+            return Err(format!("{}: texture '{}' references itself", path, name));
+        }
+        let descs = self.texture_descs;
+        let desc = descs
+            .get(name)
+            .ok_or_else(|| format!("{}: unknown texture '{}'", path, name))?;
+        self.building.push(name.to_string());
+        let texture = self.build_texture(desc, &format!("textures.{}", name))?;
+        self.building.pop();
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    fn texture_ref(
+        &mut self,
+        texture: &TextureRef,
+        path: &str,
+    ) -> Result<Arc<dyn Texture>, String> {
+        match texture {
+            TextureRef::Color(c) => Ok(Arc::new(SolidColor::new(vector(*c)))),
+            TextureRef::Name(name) => self.texture(name, path),
+        }
+    }
+
+    fn build_texture(
+        &mut self,
+        desc: &TextureDesc,
+        path: &str,
+    ) -> Result<Arc<dyn Texture>, String> {
+        let field = |name: &str| format!("{}.{}", path, name);
+        let octaves_of = |octaves: usize| {
+            if octaves > 0 {
+                Ok(octaves)
+            } else {
+                Err(format!("{}.octaves must be > 0", path))
+            }
+        };
+        let texture: Arc<dyn Texture> = match desc {
+            TextureDesc::Solid { color } => Arc::new(SolidColor::new(vector(*color))),
+            TextureDesc::Checker { odd, even } => Arc::new(CheckerTexture::new(
+                self.texture_ref(odd, &field("odd"))?,
+                self.texture_ref(even, &field("even"))?,
+            )),
+            TextureDesc::Noise { scale } => Arc::new(NoiseTexture::new(
+                positive(*scale, &field("scale"))?,
+                self.rng,
+            )),
+            TextureDesc::Turbulence {
+                scale,
+                octaves,
+                color0,
+                color1,
+            } => {
+                let scale = positive(*scale, &field("scale"))?;
+                let octaves = octaves_of(*octaves)?;
+                let color0 = self.texture_ref(color0, &field("color0"))?;
+                let color1 = self.texture_ref(color1, &field("color1"))?;
+                Arc::new(TurbulenceTexture::new(
+                    scale, octaves, color0, color1, self.rng,
+                ))
+            }
+            TextureDesc::Marble {
+                scale,
+                octaves,
+                axis,
+                color0,
+                color1,
+            } => {
+                let scale = positive(*scale, &field("scale"))?;
+                let octaves = octaves_of(*octaves)?;
+                let color0 = self.texture_ref(color0, &field("color0"))?;
+                let color1 = self.texture_ref(color1, &field("color1"))?;
+                Arc::new(MarbleTexture::new(
+                    scale, octaves, *axis, color0, color1, self.rng,
+                ))
+            }
+            TextureDesc::Wood {
+                scale,
+                octaves,
+                axis,
+                color0,
+                color1,
+            } => {
+                let scale = positive(*scale, &field("scale"))?;
+                let octaves = octaves_of(*octaves)?;
+                let color0 = self.texture_ref(color0, &field("color0"))?;
+                let color1 = self.texture_ref(color1, &field("color1"))?;
+                Arc::new(WoodTexture::new(
+                    scale, octaves, *axis, color0, color1, self.rng,
+                ))
+            }
+            TextureDesc::Image {
+                file,
+                wrap,
+                filter,
+                srgb,
+            } => Arc::new(
+                ImageTexture::open_with(self.directory.join(file), *wrap, *filter, *srgb)
+                    .map_err(|e| format!("{}: {}", field("file"), e))?,
+            ),
+        };
+        Ok(texture)
+    }
+
+    fn build_material(
+        &mut self,
+        desc: &MaterialDesc,
+        path: &str,
+    ) -> Result<Arc<dyn Material>, String> {
+        let field = |name: &str| format!("{}.{}", path, name);
+        let material: Arc<dyn Material> = match desc {
+            MaterialDesc::Lambertian { albedo } => {
+                Arc::new(Lambertian::new(self.texture_ref(albedo, &field("albedo"))?))
+            }
+            MaterialDesc::Metal { albedo, fuzz } => {
+                if !(0.0..=1.0).contains(fuzz) {
+                    return Err(format!("{} must be in [0, 1]", field("fuzz")));
+                }
+                Arc::new(Metal::new(vector(*albedo), *fuzz))
+            }
+            MaterialDesc::Dielectric { ior } => {
+                Arc::new(Dielectric::new(positive(*ior, &field("ior"))?))
+            }
+            MaterialDesc::DiffuseLight { emit } => {
+                Arc::new(DiffuseLight::new(self.texture_ref(emit, &field("emit"))?))
+            }
+            MaterialDesc::Isotropic { albedo } => {
+                Arc::new(Isotropic::new(self.texture_ref(albedo, &field("albedo"))?))
+            }
+        };
+        Ok(material)
+    }
+
+    fn material(&self, name: &str, path: &str) -> Result<Arc<dyn Material>, String> {
+        self.materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("{}: unknown material '{}'", path, name))
+    }
+
+    /// 简单形状使用material；网格使用文件自带的材质，介质使用albedo
+    fn build_shape(
+        &mut self,
+        shape: &ShapeDesc,
+        material: Option<Arc<dyn Material>>,
+        path: &str,
+    ) -> Result<Box<dyn Hitable>, String> {
+        let field = |name: &str| format!("{}.{}", path, name);
+        if shape.is_primitive() && material.is_none() {
+            return Err(format!("{} is required", field("material")));
+        }
+        if !shape.is_primitive() && material.is_some() {
+            return Err(format!("{} is not used by this shape", field("material")));
+        }
+        let material = || material.clone().unwrap();
+        let hitable: Box<dyn Hitable> = match shape {
+            ShapeDesc::Sphere { center, radius } => {
+                let radius = positive(*radius, &field("radius"))?;
+                Box::new(Sphere::with_material(vector(*center), radius, material()))
+            }
+            ShapeDesc::MovingSphere {
+                center0,
+                center1,
+                radius,
+                time0,
+                time1,
+            } => {
+                let radius = positive(*radius, &field("radius"))?;
+                interval(*time0, *time1, path, "time0", "time1")?;
+                Box::new(MovingSphere::with_material(
+                    vector(*center0),
+                    vector(*center1),
+                    *time0,
+                    *time1,
+                    radius,
+                    material(),
+                ))
+            }
+            ShapeDesc::XyRect { x0, x1, y0, y1, k } => {
+                interval(*x0, *x1, path, "x0", "x1")?;
+                interval(*y0, *y1, path, "y0", "y1")?;
+                Box::new(XYRect::with_material(*x0, *x1, *y0, *y1, *k, material()))
+            }
+            ShapeDesc::XzRect { x0, x1, z0, z1, k } => {
+                interval(*x0, *x1, path, "x0", "x1")?;
+                interval(*z0, *z1, path, "z0", "z1")?;
+                Box::new(XZRect::with_material(*x0, *x1, *z0, *z1, *k, material()))
+            }
+            ShapeDesc::YzRect { y0, y1, z0, z1, k } => {
+                interval(*y0, *y1, path, "y0", "y1")?;
+                interval(*z0, *z1, path, "z0", "z1")?;
+                Box::new(YZRect::with_material(*y0, *y1, *z0, *z1, *k, material()))
+            }
+            ShapeDesc::Box { min, max } => Box::new(Cuboid::with_material(
+                vector(*min),
+                vector(*max),
+                material(),
+            )),
+            ShapeDesc::Triangle { vertices } => Box::new(Triangle::with_material(
+                vector(vertices[0]),
+                vector(vertices[1]),
+                vector(vertices[2]),
+                material(),
+            )),
+            ShapeDesc::Mesh { file } => {
+                let file = self.directory.join(file);
+                let extension = file
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_ascii_lowercase);
+                let error = |e: &dyn std::error::Error| format!("{}: {}", field("file"), e);
+                match extension.as_deref() {
+                    Some("obj") => Box::new(load_obj(&file).map_err(|e| error(&e))?),
+                    Some("ply") => Box::new(load_ply(&file).map_err(|e| error(&e))?),
+                    _ => return Err(format!("{} must be an .obj or .ply file", field("file"))),
+                }
+            }
+            ShapeDesc::Medium {
+                boundary,
+                density,
+                albedo,
+            } => {
+                let boundary_path = field("boundary");
+                if !boundary.is_primitive() {
+                    return Err(format!("{} must be a simple shape", boundary_path));
+                }
+                // 边界只用来求交，材质不会被用到
+                let unused: Arc<dyn Material> =
+                    Arc::new(Lambertian::new(SolidColor::new(Vector3::zeros())));
+                let boundary = self.build_shape(boundary, Some(unused), &boundary_path)?;
+                let density = positive(*density, &field("density"))?;
+                let albedo = self.texture_ref(albedo, &field("albedo"))?;
+                Box::new(ConstantMedium::new(boundary, density, albedo))
+            }
+        };
+        Ok(hitable)
+    }
+}
+
+/// 依次施加变换
+fn apply_transforms(
+    mut hitable: Box<dyn Hitable>,
+    flip: bool,
+    transforms: &[TransformDesc],
+    path: &str,
+) -> Result<Box<dyn Hitable>, String> {
+    if flip {
+        hitable = Box::new(FlipFace::new(hitable));
+    }
+    for (i, transform) in transforms.iter().enumerate() {
+        hitable = match transform {
+            TransformDesc::Translate(offset) => Box::new(Translate::new(hitable, vector(*offset))),
+            TransformDesc::RotateX(degrees) => Box::new(RotateX::new(hitable, *degrees)),
+            TransformDesc::RotateY(degrees) => Box::new(RotateY::new(hitable, *degrees)),
+            TransformDesc::RotateZ(degrees) => Box::new(RotateZ::new(hitable, *degrees)),
+            TransformDesc::Scale(scale) => {
+                if !scale.iter().all(|s| s.is_finite()) {
+                    return Err(format!("{}.transforms[{}].scale must be finite", path, i));
+                }
+                if scale.contains(&0.0) {
+                    return Err(format!(
+                        "{}.transforms[{}].scale must not contain 0",
+                        path, i
+                    ));
+                }
+                let matrix = Matrix4::new_nonuniform_scaling(&vector(*scale));
+                Box::new(
+                    Transform::new(hitable, matrix)
+                        .ok_or_else(|| format!("{}.transforms[{}] is not invertible", path, i))?,
+                )
+            }
+        };
+    }
+    Ok(hitable)
+}
+
+fn build_scene(desc: &SceneDesc, directory: &Path, rng: &mut RenderRng) -> Result<Scene, String> {
+    check_image(&desc.image)?;
+    let camera = build_camera(&desc.camera, desc.image.aspect_ratio)?;
+
+    let mut builder = Builder {
+        directory,
+        texture_descs: &desc.textures,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        building: Vec::new(),
+        rng,
+    };
+    // 按名字顺序构建全部纹理与材质，未被引用的也要检查
+    for name in desc.textures.keys() {
+        builder.texture(name, &format!("textures.{}", name))?;
+    }
+    for (name, material) in &desc.materials {
+        let material = builder.build_material(material, &format!("materials.{}", name))?;
+        builder.materials.insert(name.clone(), material);
+    }
+
+    let background: Box<dyn Background> = match &desc.background {
+        BackgroundDesc::Solid { color } => Box::new(SolidBackground::new(vector(*color))),
+        BackgroundDesc::Gradient { bottom, top } => {
+            Box::new(GradientBackground::new(vector(*bottom), vector(*top)))
+        }
+        BackgroundDesc::Sky => Box::new(GradientBackground::sky()),
+        BackgroundDesc::Texture { texture } => Box::new(TextureBackground::new(
+            builder.texture_ref(texture, "background.texture")?,
+        )),
+    };
+
+    let mut world: Vec<Box<dyn Hitable>> = Vec::new();
+    for (i, object) in desc.objects.iter().enumerate() {
+        let path = format!("objects[{}]", i);
+        let material = match &object.material {
+            Some(name) => Some(builder.material(name, &format!("{}.material", path))?),
+            None => None,
+        };
+        let hitable = builder.build_shape(&object.shape, material, &path)?;
+        world.push(apply_transforms(
+            hitable,
+            object.flip,
+            &object.transforms,
+            &path,
+        )?);
+    }
+    for (i, light) in desc.lights.iter().enumerate() {
+        let path = format!("lights[{}]", i);
+        if !light.shape.is_primitive() {
+            return Err(format!("{}.type must be a simple shape", path));
+        }
+        if !light.emit.iter().all(|c| *c >= 0.0) {
+            return Err(format!("{}.emit must be >= 0", path));
+        }
+        let material: Arc<dyn Material> =
+            Arc::new(DiffuseLight::new(SolidColor::new(vector(light.emit))));
+        let hitable = builder.build_shape(&light.shape, Some(material), &path)?;
+        world.push(apply_transforms(
+            hitable,
+            light.flip,
+            &light.transforms,
+            &path,
+        )?);
+    }
+    // 没有任何物体的场景也是合法的，只能看到背景
+    let world = BVH::new(world, desc.camera.time0, desc.camera.time1).map_err(|e| match e {
+        // world中物体在前、光源在后，把下标换回文件中的条目
+        BVHError::InvalidBounds { index } => {
+            let path = match index.checked_sub(desc.objects.len()) {
+                None => format!("objects[{}]", index),
+                Some(i) => format!("lights[{}]", i),
+            };
+            format!("{}: bounding box has NaN coordinates", path)
+        }
+        e => e.to_string(),
+    })?;
+    Ok(Scene {
+        world,
+        background,
+        camera,
+    })
+}
+
+/// 读取TOML场景文件，构建图像参数与场景（物体放在BVH中）
+/// 纹理与材质在textures/materials中按名字定义，可被多处引用；
+/// 文件中的相对路径相对于场景文件所在的目录
+/// overrides：在检查与构建相机之前覆盖文件中的图像参数
+/// rng：用于噪声等程序纹理
+pub fn load_scene(
+    path: impl AsRef<Path>,
+    overrides: &ImageOverrides,
+    rng: &mut RenderRng,
+) -> Result<SceneFile, SceneError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|source| SceneError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut desc: SceneDesc = toml::from_str(&text).map_err(|source| SceneError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    overrides.apply(&mut desc.image);
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let scene = build_scene(&desc, directory, rng).map_err(|message| SceneError::Invalid {
+        path: path.to_path_buf(),
+        message,
+    })?;
+    Ok(SceneFile {
+        image: desc.image,
+        scene,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: &str = r#"
+        [camera]
+        look_from = [0, 0, 5]
+        look_at = [0, 0, 0]
+        vfov = 40
+
+        [materials.gray]
+        type = "lambertian"
+        albedo = [0.5, 0.5, 0.5]
+    "#;
+
+    fn build(body: &str) -> Result<Scene, String> {
+        let text = format!("{}\n{}", CAMERA, body);
+        let desc: SceneDesc = toml::from_str(&text).map_err(|e| e.to_string())?;
+        build_scene(&desc, Path::new(""), &mut RenderRng::from_seed(0))
+    }
+
+    fn error(body: &str) -> String {
+        match build(body) {
+            Ok(_) => panic!("scene should be rejected:\n{}", body),
+            Err(message) => message,
+        }
+    }
+
+    #[test]
+    fn accepts_valid_scene() {
+        build(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            material = "gray"
+
+            [[lights]]
+            type = "xz_rect"
+            x0 = -1
+            x1 = 1
+            z0 = -1
+            z1 = 1
+            k = 3
+            emit = [4, 4, 4]
+            "#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn rejects_non_positive_sphere_radius() {
+        for radius in ["0", "-1", "nan"] {
+            let message = error(&format!(
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = {}
+                material = "gray"
+                "#,
+                radius
+            ));
+            assert_eq!(message, "objects[0].radius must be > 0");
+        }
+    }
+
+    #[test]
+    fn rejects_non_positive_moving_sphere_radius() {
+        let message = error(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            material = "gray"
+
+            [[objects]]
+            type = "moving_sphere"
+            center0 = [0, 0, 0]
+            center1 = [0, 1, 0]
+            radius = -0.5
+            time0 = 0
+            time1 = 1
+            material = "gray"
+            "#,
+        );
+        assert_eq!(message, "objects[1].radius must be > 0");
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let cases = [
+            (
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = 1
+                "#,
+                "objects[0].material is required",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = 1
+                material = "missing"
+                "#,
+                "objects[0].material: unknown material 'missing'",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "xy_rect"
+                x0 = 1
+                x1 = 1
+                y0 = 0
+                y1 = 1
+                k = 0
+                material = "gray"
+                "#,
+                "objects[0].x1 must be > x0",
+            ),
+            (
+                r#"
+                [materials.rough]
+                type = "metal"
+                albedo = [1, 1, 1]
+                fuzz = 2
+                "#,
+                "materials.rough.fuzz must be in [0, 1]",
+            ),
+            (
+                r#"
+                [materials.glass]
+                type = "dielectric"
+                ior = -1.5
+                "#,
+                "materials.glass.ior must be > 0",
+            ),
+            (
+                r#"
+                [textures.loop]
+                type = "checker"
+                odd = "loop"
+                even = [1, 1, 1]
+                "#,
+                "textures.loop.odd: texture 'loop' references itself",
+            ),
+            (
+                r#"
+                [textures.noise]
+                type = "turbulence"
+                scale = 1
+                octaves = 0
+                color0 = [0, 0, 0]
+                color1 = [1, 1, 1]
+                "#,
+                "textures.noise.octaves must be > 0",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "mesh"
+                file = "model.stl"
+                "#,
+                "objects[0].file must be an .obj or .ply file",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "medium"
+                density = 1
+                albedo = [1, 1, 1]
+                boundary = { type = "mesh", file = "model.obj" }
+                "#,
+                "objects[0].boundary must be a simple shape",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "box"
+                min = [0, 0, 0]
+                max = [1, 1, 1]
+                material = "gray"
+                transforms = [{ scale = [1, 0, 1] }]
+                "#,
+                "objects[0].transforms[0].scale must not contain 0",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = 1
+                material = "gray"
+                transforms = [{ translate = [1, 0, 0] }, { scale = [1, inf, 1] }]
+                "#,
+                "objects[0].transforms[1].scale must be finite",
+            ),
+            (
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = 1
+                material = "gray"
+                transforms = [{ scale = [1e-110, 1e-110, 1e-110] }]
+                "#,
+                "objects[0].transforms[0] is not invertible",
+            ),
+            (
+                r#"
+                [[lights]]
+                type = "sphere"
+                center = [0, 0, 0]
+                radius = 1
+                emit = [1, nan, 1]
+                "#,
+                "lights[0].emit must be >= 0",
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(error(body), expected);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_image_and_camera() {
+        let sphere = r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            material = "gray"
+        "#;
+        let with_image = |image: &str| {
+            let text = format!("[image]\n{}\n{}\n{}", image, CAMERA, sphere);
+            let desc: SceneDesc = toml::from_str(&text).unwrap();
+            match build_scene(&desc, Path::new(""), &mut RenderRng::from_seed(0)) {
+                Ok(_) => panic!("image settings should be rejected: {}", image),
+                Err(message) => message,
+            }
+        };
+        assert_eq!(with_image("width = 0"), "image.width must be > 0");
+        assert_eq!(
+            with_image("samples_per_pixel = 0"),
+            "image.samples_per_pixel must be > 0"
+        );
+        assert_eq!(
+            with_image("aspect_ratio = -1"),
+            "image.aspect_ratio must be > 0"
+        );
+    }
+
+    #[test]
+    fn accepts_empty_scene() {
+        build("").unwrap();
+    }
+
+    #[test]
+    fn reports_invalid_bounds_at_the_right_entry() {
+        let message = error(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            material = "gray"
+
+            [[lights]]
+            type = "sphere"
+            center = [0, 0, 3]
+            radius = 1
+            emit = [1, 1, 1]
+
+            [[lights]]
+            type = "sphere"
+            center = [nan, 0, 3]
+            radius = 1
+            emit = [1, 1, 1]
+            "#,
+        );
+        assert_eq!(message, "lights[1]: bounding box has NaN coordinates");
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let message = error(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            radios = 2
+            material = "gray"
+            "#,
+        );
+        assert!(message.contains("radios"), "{}", message);
+    }
+}
@@ -0,0 +1,682 @@
+//! 内置场景
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use na::Vector3;
+use nalgebra as na;
+use rand::Rng;
+
+use crate::background::{GradientBackground, SolidBackground, TextureBackground};
+use crate::bvh::BVH;
+use crate::camera::Camera;
+use crate::cuboid::Cuboid;
+use crate::hitable::{FlipFace, Hitable, HitableList};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::medium::ConstantMedium;
+use crate::obj::{load_obj, ObjError};
+use crate::ply::{load_ply, PlyError};
+use crate::rect::{XYRect, XZRect, YZRect};
+use crate::rng::RenderRng;
+use crate::scene::Scene;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{
+    Axis, CheckerTexture, FilterMode, ImageTexture, ImageTextureError, MarbleTexture, NoiseTexture,
+    SolidColor, TurbulenceTexture, WoodTexture, WrapMode,
+};
+use crate::transform::{RotateX, RotateY, RotateZ, Transform, Translate};
+use crate::triangle::Triangle;
+
+/// 内置场景读取资源文件时的错误
+#[derive(Debug)]
+pub enum AssetError {
+    Obj(ObjError),
+    Ply(PlyError),
+    Image(ImageTextureError),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Obj(e) => e.fmt(f),
+            AssetError::Ply(e) => e.fmt(f),
+            AssetError::Image(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Obj(e) => Some(e),
+            AssetError::Ply(e) => Some(e),
+            AssetError::Image(e) => Some(e),
+        }
+    }
+}
+
+impl From<ObjError> for AssetError {
+    fn from(e: ObjError) -> Self {
+        AssetError::Obj(e)
+    }
+}
+
+impl From<PlyError> for AssetError {
+    fn from(e: PlyError) -> Self {
+        AssetError::Ply(e)
+    }
+}
+
+impl From<ImageTextureError> for AssetError {
+    fn from(e: ImageTextureError) -> Self {
+        AssetError::Image(e)
+    }
+}
+
+/// 资源文件的路径，按crate所在目录定位，与当前工作目录无关
+fn asset(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join(name)
+}
+
+/// 内置场景的物体都有合法的包围盒，构建不会失败
+fn bvh(world: impl Into<Vec<Box<dyn Hitable>>>) -> BVH {
+    BVH::new(world.into(), 0.0, 1.0).expect("built-in scenes have valid bounding boxes")
+}
+
+/// 默认相机，从(13, 2, 3)看向原点
+fn default_camera(aspect_ratio: f64) -> Camera {
+    Camera::new(
+        Vector3::new(13.0, 2.0, 3.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        20.0,
+        aspect_ratio,
+        0.1,
+        10.0,
+        0.0,
+        1.0,
+    )
+}
+
+// 物体为BVH树的根节点，Box<BVH>
+fn random_scene(aspect_ratio: f64, rng: &mut RenderRng) -> Scene {
+    let origin = Vector3::new(4.0, 0.2, 0.0);
+    let mut world: Vec<Box<dyn Hitable>> = Vec::new();
+    let checker = CheckerTexture::new(
+        SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
+        SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
+    );
+    world.push(Box::new(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(checker),
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_material = rng.gen::<f64>();
+            let center = Vector3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            if (center - origin).magnitude() > 0.9 {
+                if choose_material < 0.8 {
+                    // diffuse
+                    world.push(Box::new(MovingSphere::new(
+                        center,
+                        center + Vector3::new(0.0, rng.gen_range(0.0..0.5), 0.0),
+                        0.0,
+                        1.0,
+                        0.2,
+                        Lambertian::new(SolidColor::new(Vector3::new(
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                        ))),
+                    )));
+                } else if choose_material < 0.95 {
+                    // metal
+                    world.push(Box::new(Sphere::new(
+                        center,
+                        0.2,
+                        Metal::new(
+                            Vector3::new(
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                            ),
+                            0.5 * rng.gen::<f64>(),
+                        ),
+                    )));
+                } else {
+                    // glass
+                    world.push(Box::new(Sphere::new(center, 0.2, Dielectric::new(1.5))));
+                }
+            }
+        }
+    }
+    world.push(Box::new(Sphere::new(
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        Dielectric::new(1.5),
+    )));
+    world.push(Box::new(Sphere::new(
+        Vector3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(SolidColor::new(Vector3::new(0.4, 0.2, 0.1))),
+    )));
+    world.push(Box::new(Sphere::new(
+        Vector3::new(4.0, 1.0, 0.0),
+        1.0,
+        Metal::new(Vector3::new(0.7, 0.6, 0.5), 0.0),
+    )));
+    Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+fn two_spheres(aspect_ratio: f64, rng: &mut RenderRng) -> Scene {
+    // let texture1 = CheckerTexture::new(
+    //   SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
+    //   SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
+    // );
+    let texture1 = NoiseTexture::new(4.0, rng);
+    let texture2 = NoiseTexture::new(4.0, rng);
+
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(texture1),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(texture2),
+    ));
+    Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+fn simple_light(aspect_ratio: f64, rng: &mut RenderRng) -> Scene {
+    let texture1 = NoiseTexture::new(4.0, rng);
+    let texture2 = NoiseTexture::new(4.0, rng);
+
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(texture1),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(texture2),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 7.0, 0.0),
+        2.0,
+        DiffuseLight::new(SolidColor::new(Vector3::new(4.0, 4.0, 4.0))),
+    ));
+    // 只由光源照亮
+    Scene {
+        world: bvh(world),
+        background: Box::new(SolidBackground::new(Vector3::new(0.0, 0.0, 0.0))),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+fn checker_sky(aspect_ratio: f64) -> Scene {
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        2.0,
+        Metal::new(Vector3::new(0.7, 0.6, 0.5), 0.0),
+    ));
+    // 用方向查找的棋盘格环境
+    let sky = CheckerTexture::new(
+        SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
+        SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
+    );
+    Scene {
+        world: bvh(world),
+        background: Box::new(TextureBackground::new(sky)),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+/// Cornell box的墙壁与顶灯，法线都朝向盒子内部
+fn cornell_walls(light: f64) -> HitableList {
+    let red = || Lambertian::new(SolidColor::new(Vector3::new(0.65, 0.05, 0.05)));
+    let white = || Lambertian::new(SolidColor::new(Vector3::new(0.73, 0.73, 0.73)));
+    let green = || Lambertian::new(SolidColor::new(Vector3::new(0.12, 0.45, 0.15)));
+    let light = DiffuseLight::new(SolidColor::new(Vector3::new(light, light, light)));
+
+    let mut world = HitableList::new();
+    world.push(FlipFace::new(YZRect::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        green(),
+    )));
+    world.push(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red()));
+    world.push(FlipFace::new(XZRect::new(
+        213.0, 343.0, 227.0, 332.0, 554.0, light,
+    )));
+    world.push(XZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, white()));
+    world.push(FlipFace::new(XZRect::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        white(),
+    )));
+    world.push(FlipFace::new(XYRect::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        white(),
+    )));
+    world
+}
+
+fn cornell_camera(aspect_ratio: f64) -> Camera {
+    Camera::new(
+        Vector3::new(278.0, 278.0, -800.0),
+        Vector3::new(278.0, 278.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        40.0,
+        aspect_ratio,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    )
+}
+
+fn cornell_box(aspect_ratio: f64) -> Scene {
+    let white = || Lambertian::new(SolidColor::new(Vector3::new(0.73, 0.73, 0.73)));
+    let mut world = cornell_walls(15.0);
+
+    // 先绕y轴旋转，再平移到位
+    let box1 = Cuboid::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(165.0, 330.0, 165.0),
+        white(),
+    );
+    world.push(Translate::new(
+        RotateY::new(box1, 15.0),
+        Vector3::new(265.0, 0.0, 295.0),
+    ));
+    let box2 = Cuboid::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(165.0, 165.0, 165.0),
+        white(),
+    );
+    world.push(Translate::new(
+        RotateY::new(box2, -18.0),
+        Vector3::new(130.0, 0.0, 65.0),
+    ));
+
+    Scene {
+        world: bvh(world),
+        background: Box::new(SolidBackground::new(Vector3::new(0.0, 0.0, 0.0))),
+        camera: cornell_camera(aspect_ratio),
+    }
+}
+
+/// Cornell box中的烟雾盒，以及充满介质的玻璃球
+fn cornell_smoke(aspect_ratio: f64) -> Scene {
+    let white = || Lambertian::new(SolidColor::new(Vector3::new(0.73, 0.73, 0.73)));
+    let mut world = cornell_walls(7.0);
+
+    let box1 = Cuboid::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(165.0, 330.0, 165.0),
+        white(),
+    );
+    world.push(ConstantMedium::new(
+        Translate::new(RotateY::new(box1, 15.0), Vector3::new(265.0, 0.0, 295.0)),
+        0.01,
+        SolidColor::new(Vector3::new(0.0, 0.0, 0.0)),
+    ));
+
+    // 玻璃外壳加内部的介质，近似次表面散射
+    let center = Vector3::new(190.0, 90.0, 190.0);
+    world.push(Sphere::new(center, 90.0, Dielectric::new(1.5)));
+    world.push(ConstantMedium::new(
+        Sphere::new(center, 90.0, Dielectric::new(1.5)),
+        0.05,
+        SolidColor::new(Vector3::new(0.2, 0.4, 0.9)),
+    ));
+
+    Scene {
+        world: bvh(world),
+        background: Box::new(SolidBackground::new(Vector3::new(0.0, 0.0, 0.0))),
+        camera: cornell_camera(aspect_ratio),
+    }
+}
+
+fn transformed_shapes(aspect_ratio: f64) -> Scene {
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5))),
+    ));
+    let unit_box = || {
+        Cuboid::new(
+            Vector3::new(-0.5, -0.5, -0.5),
+            Vector3::new(0.5, 0.5, 0.5),
+            Lambertian::new(SolidColor::new(Vector3::new(0.8, 0.3, 0.2))),
+        )
+    };
+    world.push(Translate::new(
+        RotateX::new(unit_box(), 30.0),
+        Vector3::new(0.0, 0.8, -2.0),
+    ));
+    world.push(Translate::new(
+        RotateZ::new(unit_box(), 30.0),
+        Vector3::new(0.0, 0.8, 2.0),
+    ));
+    // 由单位球缩放、平移得到的椭球
+    let ellipsoid = na::Matrix4::new_translation(&Vector3::new(0.0, 1.0, 0.0))
+        * na::Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 0.5, 0.5));
+    world.push(
+        Transform::new(
+            Sphere::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                1.0,
+                Metal::new(Vector3::new(0.7, 0.6, 0.5), 0.1),
+            ),
+            ellipsoid,
+        )
+        .expect("ellipsoid transform is invertible"),
+    );
+    Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+/// 由三角形拼成的波浪面，顶点法线平滑着色，放入BVH
+fn wave_surface(aspect_ratio: f64) -> Scene {
+    const N: usize = 40;
+    let height = |x: f64, z: f64| 0.3 * (x * 2.0).sin() * (z * 2.0).cos();
+    // 高度场的解析法线
+    let normal = |x: f64, z: f64| {
+        Vector3::new(
+            -0.6 * (x * 2.0).cos() * (z * 2.0).cos(),
+            1.0,
+            0.6 * (x * 2.0).sin() * (z * 2.0).sin(),
+        )
+        .normalize()
+    };
+    let point = |i: usize, j: usize| {
+        let x = -4.0 + 8.0 * i as f64 / N as f64;
+        let z = -4.0 + 8.0 * j as f64 / N as f64;
+        (
+            Vector3::new(x, height(x, z), z),
+            normal(x, z),
+            (i as f64 / N as f64, j as f64 / N as f64),
+        )
+    };
+    // 所有三角形共享同一个棋盘格材质
+    let checker: Arc<dyn Material> = Arc::new(Lambertian::new(CheckerTexture::new(
+        SolidColor::new(Vector3::new(0.2, 0.3, 0.1)),
+        SolidColor::new(Vector3::new(0.9, 0.9, 0.9)),
+    )));
+
+    let mut world: Vec<Box<dyn Hitable>> = Vec::new();
+    for i in 0..N {
+        for j in 0..N {
+            let (p00, n00, uv00) = point(i, j);
+            let (p10, n10, uv10) = point(i + 1, j);
+            let (p01, n01, uv01) = point(i, j + 1);
+            let (p11, n11, uv11) = point(i + 1, j + 1);
+            // 逆时针，法线朝上
+            world.push(Box::new(Triangle::with_attributes(
+                [p00, p01, p10],
+                Some([n00, n01, n10]),
+                Some([uv00, uv01, uv10]),
+                checker.clone(),
+            )));
+            world.push(Box::new(Triangle::with_attributes(
+                [p10, p01, p11],
+                Some([n10, n01, n11]),
+                Some([uv10, uv01, uv11]),
+                checker.clone(),
+            )));
+        }
+    }
+    world.push(Box::new(Triangle::new(
+        Vector3::new(-1.0, 0.5, 0.0),
+        Vector3::new(1.0, 0.5, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+        Metal::new(Vector3::new(0.7, 0.6, 0.5), 0.0),
+    )));
+    Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+/// 从OBJ文件加载的网格
+fn obj_model(aspect_ratio: f64) -> Result<Scene, AssetError> {
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5))),
+    ));
+    let cube = load_obj(asset("cube.obj"))?;
+    world.push(Translate::new(
+        RotateY::new(cube, 30.0),
+        Vector3::new(0.0, 0.5, 0.0),
+    ));
+    Ok(Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    })
+}
+
+/// 从PLY文件加载的顶点着色网格
+fn ply_model(aspect_ratio: f64) -> Result<Scene, AssetError> {
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5))),
+    ));
+    let tetrahedron = load_ply(asset("tetrahedron.ply"))?;
+    world.push(Translate::new(tetrahedron, Vector3::new(0.0, 1.0, 0.0)));
+    Ok(Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    })
+}
+
+/// 三种环绕方式与两种过滤方式的图像纹理
+/// 四边形的uv范围是[-1, 2]，可以看到纹理在边界外的表现
+fn image_textures(aspect_ratio: f64) -> Result<Scene, AssetError> {
+    let mut world: Vec<Box<dyn Hitable>> = Vec::new();
+    let modes = [
+        (WrapMode::Repeat, FilterMode::Bilinear),
+        (WrapMode::Clamp, FilterMode::Bilinear),
+        (WrapMode::Mirror, FilterMode::Nearest),
+    ];
+    for (i, (wrap, filter)) in modes.into_iter().enumerate() {
+        let texture = ImageTexture::open_with(asset("uv_grid.png"), wrap, filter, true)?;
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(texture));
+        // 在z=0平面上竖立的正方形，由两个三角形组成
+        let x0 = -3.3 + 2.2 * i as f64;
+        let corners = [
+            (Vector3::new(x0, 0.0, 0.0), (-1.0, -1.0)),
+            (Vector3::new(x0 + 2.0, 0.0, 0.0), (2.0, -1.0)),
+            (Vector3::new(x0 + 2.0, 2.0, 0.0), (2.0, 2.0)),
+            (Vector3::new(x0, 2.0, 0.0), (-1.0, 2.0)),
+        ];
+        for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+            world.push(Box::new(Triangle::with_attributes(
+                [corners[a].0, corners[b].0, corners[c].0],
+                None,
+                Some([corners[a].1, corners[b].1, corners[c].1]),
+                material.clone(),
+            )));
+        }
+    }
+    Ok(Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: Camera::new(
+            Vector3::new(0.0, 1.0, 8.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            40.0,
+            aspect_ratio,
+            0.0,
+            8.0,
+            0.0,
+            1.0,
+        ),
+    })
+}
+
+/// 湍流、大理石与木纹三种程序纹理
+fn procedural_textures(aspect_ratio: f64, rng: &mut RenderRng) -> Scene {
+    let color = |r, g, b| SolidColor::new(Vector3::new(r, g, b));
+    let mut world = HitableList::new();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(TurbulenceTexture::new(
+            1.0,
+            7,
+            color(0.1, 0.1, 0.1),
+            color(0.9, 0.9, 0.9),
+            rng,
+        )),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 1.0, -2.2),
+        1.0,
+        Lambertian::new(MarbleTexture::new(
+            4.0,
+            7,
+            Axis::X,
+            color(0.9, 0.9, 0.85),
+            color(0.15, 0.15, 0.2),
+            rng,
+        )),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(WoodTexture::new(
+            8.0,
+            4,
+            Axis::Y,
+            color(0.55, 0.35, 0.15),
+            color(0.3, 0.15, 0.05),
+            rng,
+        )),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 1.0, 2.2),
+        1.0,
+        Lambertian::new(MarbleTexture::new(
+            4.0,
+            5,
+            Axis::Z,
+            color(0.8, 0.2, 0.1),
+            color(0.95, 0.9, 0.8),
+            rng,
+        )),
+    ));
+    Scene {
+        world: bvh(world),
+        background: Box::new(GradientBackground::sky()),
+        camera: default_camera(aspect_ratio),
+    }
+}
+
+/// 构造内置场景，参数为图像宽高比与构造场景用的随机数生成器
+/// 用到模型或图像文件的场景在读取失败时返回错误
+pub type SceneFn = fn(f64, &mut RenderRng) -> Result<Scene, AssetError>;
+
+/// 全部内置场景及其名字
+pub const SCENES: &[(&str, SceneFn)] = &[
+    ("random", |aspect_ratio, rng| {
+        Ok(random_scene(aspect_ratio, rng))
+    }),
+    ("two-spheres", |aspect_ratio, rng| {
+        Ok(two_spheres(aspect_ratio, rng))
+    }),
+    ("simple-light", |aspect_ratio, rng| {
+        Ok(simple_light(aspect_ratio, rng))
+    }),
+    ("checker-sky", |aspect_ratio, _| {
+        Ok(checker_sky(aspect_ratio))
+    }),
+    ("cornell-box", |aspect_ratio, _| {
+        Ok(cornell_box(aspect_ratio))
+    }),
+    ("cornell-smoke", |aspect_ratio, _| {
+        Ok(cornell_smoke(aspect_ratio))
+    }),
+    ("transformed-shapes", |aspect_ratio, _| {
+        Ok(transformed_shapes(aspect_ratio))
+    }),
+    ("wave-surface", |aspect_ratio, _| {
+        Ok(wave_surface(aspect_ratio))
+    }),
+    ("obj-model", |aspect_ratio, _| obj_model(aspect_ratio)),
+    ("ply-model", |aspect_ratio, _| ply_model(aspect_ratio)),
+    ("image-textures", |aspect_ratio, _| {
+        image_textures(aspect_ratio)
+    }),
+    ("procedural-textures", |aspect_ratio, rng| {
+        Ok(procedural_textures(aspect_ratio, rng))
+    }),
+];
+
+/// 按名字查找内置场景
+pub fn find(name: &str) -> Option<SceneFn> {
+    SCENES
+        .iter()
+        .find(|(scene_name, _)| *scene_name == name)
+        .map(|(_, build)| *build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scene_builds() {
+        for (name, build) in SCENES {
+            let mut rng = RenderRng::from_seed(0);
+            if let Err(e) = build(1.5, &mut rng) {
+                panic!("{}: {}", name, e);
+            }
+        }
+    }
+}
@@ -9,17 +9,24 @@ use super::hitable::{HitRecord, Hitable};
 use super::ray::Ray;
 use nalgebra::Vector3;
 
+/// 球体
 pub struct Sphere {
     center: Vector3<f64>,
     radius: f64,
     material: Arc<dyn Material>,
 }
 impl Sphere {
+    /// 半径为负时法线朝内，可用于空心玻璃球的内表面
     pub fn new(center: Vector3<f64>, radius: f64, material: impl Material + 'static) -> Sphere {
+        Sphere::with_material(center, radius, Arc::new(material))
+    }
+
+    /// 使用已有的共享材质
+    pub fn with_material(center: Vector3<f64>, radius: f64, material: Arc<dyn Material>) -> Sphere {
         Sphere {
             center,
             radius,
-            material: Arc::new(material),
+            material,
         }
     }
 
@@ -72,6 +79,7 @@ impl Hitable for Sphere {
     }
 }
 
+/// 运动的球体，用于运动模糊
 pub struct MovingSphere {
     center0: Vector3<f64>,
     center1: Vector3<f64>,
@@ -81,6 +89,7 @@ pub struct MovingSphere {
     material: Arc<dyn Material>,
 }
 impl MovingSphere {
+    /// time0时球心在center0，time1时在center1，其间匀速运动
     pub fn new(
         center0: Vector3<f64>,
         center1: Vector3<f64>,
@@ -88,6 +97,18 @@ impl MovingSphere {
         time1: f64,
         radius: f64,
         material: impl Material + 'static,
+    ) -> MovingSphere {
+        MovingSphere::with_material(center0, center1, time0, time1, radius, Arc::new(material))
+    }
+
+    /// 与new相同，材质可与其他物体共享
+    pub fn with_material(
+        center0: Vector3<f64>,
+        center1: Vector3<f64>,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Arc<dyn Material>,
     ) -> MovingSphere {
         MovingSphere {
             center0,
@@ -95,14 +116,13 @@ impl MovingSphere {
             time0,
             time1,
             radius,
-            material: Arc::new(material),
+            material,
         }
     }
+    /// time时刻的球心，超出[time0, time1]时停在端点
     pub fn center(&self, time: f64) -> Vector3<f64> {
         self.center0
-            + ((time - self.time0) / (self.time1 - self.time0))
-                .min(1.0)
-                .max(0.0)
+            + ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0)
                 * (self.center1 - self.center0)
     }
     /// 输入单位圆表面的点
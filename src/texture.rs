@@ -1,16 +1,40 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
 use nalgebra::Vector3;
+use serde::Deserialize;
+use std::sync::Arc;
 
+use crate::hitable::HitRecord;
 use crate::perlin::Perlin;
+use crate::rng::RenderRng;
 
+/// 纹理：按uv坐标与命中点位置给出颜色
 pub trait Texture: Send + Sync {
     fn value(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64>;
+    /// 物体表面上的取值，需要命中记录中其他信息的纹理可以覆盖
+    fn value_at(&self, hit_record: &HitRecord) -> Vector3<f64> {
+        self.value(hit_record.u(), hit_record.v(), hit_record.point())
+    }
+}
+
+/// 共享纹理，使多个材质可以引用同一个纹理
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    fn value(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        self.as_ref().value(u, v, p)
+    }
+    fn value_at(&self, hit_record: &HitRecord) -> Vector3<f64> {
+        self.as_ref().value_at(hit_record)
+    }
 }
 
+/// 纯色纹理
 pub struct SolidColor {
     color_value: Vector3<f64>,
 }
 
 impl SolidColor {
+    /// 各处都是color_value
     pub fn new(color_value: Vector3<f64>) -> Self {
         SolidColor { color_value }
     }
@@ -22,12 +46,14 @@ impl Texture for SolidColor {
     }
 }
 
+/// 三维棋盘格纹理，按命中点位置在两个纹理之间交替
 pub struct CheckerTexture {
     odd: Box<dyn Texture>,
     even: Box<dyn Texture>,
 }
 
 impl CheckerTexture {
+    /// 格子的边长约为0.31（π/10）
     pub fn new(odd: impl Texture + 'static, even: impl Texture + 'static) -> CheckerTexture {
         CheckerTexture {
             odd: Box::new(odd),
@@ -47,16 +73,17 @@ impl Texture for CheckerTexture {
     }
 }
 
+/// Perlin噪声纹理，灰度
 pub struct NoiseTexture {
     noise: Perlin,
     scale: f64,
 }
 
 impl NoiseTexture {
-    /// Creates a new [`NoiseTexture`].
-    pub fn new(sc: f64) -> Self {
+    /// sc：噪声的频率，越大纹理越细
+    pub fn new(sc: f64, rng: &mut RenderRng) -> Self {
         NoiseTexture {
-            noise: Perlin::new(),
+            noise: Perlin::new(rng),
             scale: sc,
         }
     }
@@ -67,3 +94,400 @@ impl Texture for NoiseTexture {
         Vector3::new(1.0, 1.0, 1.0) * 0.5 * (self.noise.noise(&(p * self.scale)) + 1.0)
     }
 }
+
+/// 坐标轴，用于选择程序纹理的相位方向
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// 按t在两个纹理之间线性插值
+fn lerp_textures(
+    t: f64,
+    color0: &dyn Texture,
+    color1: &dyn Texture,
+    u: f64,
+    v: f64,
+    p: Vector3<f64>,
+) -> Vector3<f64> {
+    (1.0 - t) * color0.value(u, v, p) + t * color1.value(u, v, p)
+}
+
+/// 湍流纹理，按多层噪声叠加的强度在两个颜色间插值
+/// octaves：叠加的噪声层数
+pub struct TurbulenceTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    color0: Box<dyn Texture>,
+    color1: Box<dyn Texture>,
+}
+
+impl TurbulenceTexture {
+    /// scale：噪声的频率；rng用于生成噪声的随机梯度
+    pub fn new(
+        scale: f64,
+        octaves: usize,
+        color0: impl Texture + 'static,
+        color1: impl Texture + 'static,
+        rng: &mut RenderRng,
+    ) -> Self {
+        TurbulenceTexture {
+            noise: Perlin::new(rng),
+            scale,
+            octaves,
+            color0: Box::new(color0),
+            color1: Box::new(color1),
+        }
+    }
+}
+
+impl Texture for TurbulenceTexture {
+    fn value(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let t = self.noise.turb(&(p * self.scale), self.octaves).min(1.0);
+        lerp_textures(t, self.color0.as_ref(), self.color1.as_ref(), u, v, p)
+    }
+}
+
+/// 大理石纹理，沿axis方向的正弦条纹被湍流扰动
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    axis: Axis,
+    color0: Box<dyn Texture>,
+    color1: Box<dyn Texture>,
+}
+
+impl MarbleTexture {
+    /// scale：条纹的频率；rng用于生成噪声的随机梯度
+    pub fn new(
+        scale: f64,
+        octaves: usize,
+        axis: Axis,
+        color0: impl Texture + 'static,
+        color1: impl Texture + 'static,
+        rng: &mut RenderRng,
+    ) -> Self {
+        MarbleTexture {
+            noise: Perlin::new(rng),
+            scale,
+            octaves,
+            axis,
+            color0: Box::new(color0),
+            color1: Box::new(color1),
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        let phase = self.scale * p[self.axis.index()] + 10.0 * self.noise.turb(&p, self.octaves);
+        let t = 0.5 * (1.0 + phase.sin());
+        lerp_textures(t, self.color0.as_ref(), self.color1.as_ref(), u, v, p)
+    }
+}
+
+/// 木纹纹理，以axis为中心轴的同心年轮被湍流扰动
+pub struct WoodTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    axis: Axis,
+    color0: Box<dyn Texture>,
+    color1: Box<dyn Texture>,
+}
+
+impl WoodTexture {
+    /// scale：年轮的密度；rng用于生成噪声的随机梯度
+    pub fn new(
+        scale: f64,
+        octaves: usize,
+        axis: Axis,
+        color0: impl Texture + 'static,
+        color1: impl Texture + 'static,
+        rng: &mut RenderRng,
+    ) -> Self {
+        WoodTexture {
+            noise: Perlin::new(rng),
+            scale,
+            octaves,
+            axis,
+            color0: Box::new(color0),
+            color1: Box::new(color1),
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, u: f64, v: f64, p: Vector3<f64>) -> Vector3<f64> {
+        // 到中心轴的距离
+        let mut radial = p;
+        radial[self.axis.index()] = 0.0;
+        let rings = self.scale * radial.norm() + 2.0 * self.noise.turb(&p, self.octaves);
+        let t = rings - rings.floor();
+        lerp_textures(t, self.color0.as_ref(), self.color1.as_ref(), u, v, p)
+    }
+}
+
+/// 顶点颜色纹理
+/// 取网格在命中点插值得到的顶点颜色，同一网格的所有面可以共用一个
+pub struct VertexColorTexture {
+    fallback: Vector3<f64>,
+}
+
+impl VertexColorTexture {
+    /// fallback：命中的物体没有顶点颜色时使用的颜色
+    pub fn new(fallback: Vector3<f64>) -> Self {
+        VertexColorTexture { fallback }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, _u: f64, _v: f64, _p: Vector3<f64>) -> Vector3<f64> {
+        self.fallback
+    }
+    fn value_at(&self, hit_record: &HitRecord) -> Vector3<f64> {
+        hit_record.vertex_color().unwrap_or(self.fallback)
+    }
+}
+
+/// uv超出[0, 1]时的处理方式
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// 平铺
+    Repeat,
+    /// 取边缘像素
+    Clamp,
+    /// 镜像平铺
+    Mirror,
+}
+
+/// 像素之间的采样方式
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// 最近邻
+    Nearest,
+    /// 双线性插值
+    Bilinear,
+}
+
+/// 图像纹理加载错误
+#[derive(Debug)]
+pub struct ImageTextureError {
+    path: PathBuf,
+    source: image::ImageError,
+}
+
+impl fmt::Display for ImageTextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for ImageTextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// sRGB编码值转换为线性值
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 图像纹理，像素以线性RGB保存
+/// 按(u, v)采样，v=0对应图像最下面一行
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+    wrap: WrapMode,
+    filter: FilterMode,
+}
+
+impl ImageTexture {
+    /// 读取PPM/PNG/JPEG，平铺、双线性插值、按sRGB解码
+    pub fn open(path: impl AsRef<Path>) -> Result<ImageTexture, ImageTextureError> {
+        ImageTexture::open_with(path, WrapMode::Repeat, FilterMode::Bilinear, true)
+    }
+
+    /// srgb：像素是否为sRGB编码，颜色贴图一般为true，数据贴图为false
+    pub fn open_with(
+        path: impl AsRef<Path>,
+        wrap: WrapMode,
+        filter: FilterMode,
+        srgb: bool,
+    ) -> Result<ImageTexture, ImageTextureError> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|source| ImageTextureError {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .to_rgb32f();
+        let decode = |c: f32| if srgb { srgb_to_linear(c) } else { c };
+        let pixels = image
+            .pixels()
+            .map(|p| [decode(p[0]), decode(p[1]), decode(p[2])])
+            .collect();
+        Ok(ImageTexture {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels,
+            wrap,
+            filter,
+        })
+    }
+
+    /// 按环绕方式把像素坐标映射到[0, n)
+    fn wrap_index(&self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self.wrap {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m >= n {
+                    2 * n - 1 - m
+                } else {
+                    m
+                }
+            }
+        };
+        i as usize
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector3<f64> {
+        let x = self.wrap_index(x, self.width);
+        let y = self.wrap_index(y, self.height);
+        let p = self.pixels[y * self.width + x];
+        Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Vector3<f64>) -> Vector3<f64> {
+        if self.pixels.is_empty() || !u.is_finite() || !v.is_finite() {
+            return Vector3::new(0.0, 0.0, 0.0);
+        }
+        // 图像第0行在最上面，翻转v
+        let x = u * self.width as f64;
+        let y = (1.0 - v) * self.height as f64;
+        match self.filter {
+            FilterMode::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            FilterMode::Bilinear => {
+                // 以像素中心为采样点
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = (1.0 - tx) * self.texel(x0, y0) + tx * self.texel(x0 + 1, y0);
+                let bottom = (1.0 - tx) * self.texel(x0, y0 + 1) + tx * self.texel(x0 + 1, y0 + 1);
+                (1.0 - ty) * top + ty * bottom
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4x2的线性纹理，像素(x, y)的颜色为(x, y, 0)，y从上往下数
+    fn texture(wrap: WrapMode, filter: FilterMode) -> ImageTexture {
+        let (width, height) = (4, 2);
+        let pixels = (0..width * height)
+            .map(|i| [(i % width) as f32, (i / width) as f32, 0.0])
+            .collect();
+        ImageTexture {
+            width,
+            height,
+            pixels,
+            wrap,
+            filter,
+        }
+    }
+
+    fn sample(texture: &ImageTexture, u: f64, v: f64) -> Vector3<f64> {
+        texture.value(u, v, Vector3::zeros())
+    }
+
+    #[test]
+    fn wrap_modes_outside_the_unit_square() {
+        // 宽4，u = -0.25与1.25分别落在像素-1与5
+        for (wrap, left, right) in [
+            (WrapMode::Repeat, 3, 1),
+            (WrapMode::Clamp, 0, 3),
+            (WrapMode::Mirror, 0, 2),
+        ] {
+            let texture = texture(wrap, FilterMode::Nearest);
+            assert_eq!(texture.wrap_index(-1, 4), left);
+            assert_eq!(texture.wrap_index(5, 4), right);
+            assert_eq!(sample(&texture, -0.25 + 0.01, 0.75).x, left as f64);
+            assert_eq!(sample(&texture, 1.25 + 0.01, 0.75).x, right as f64);
+        }
+    }
+
+    #[test]
+    fn nearest_and_bilinear_at_centres_and_edges() {
+        let nearest = texture(WrapMode::Clamp, FilterMode::Nearest);
+        let bilinear = texture(WrapMode::Clamp, FilterMode::Bilinear);
+        // 像素(1, 0)的中心：两种方式都正好取到它
+        let (u, v) = (1.5 / 4.0, 1.0 - 0.5 / 2.0);
+        assert_eq!(sample(&nearest, u, v), Vector3::new(1.0, 0.0, 0.0));
+        assert!((sample(&bilinear, u, v) - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-12);
+        // 像素1与2的交界：最近邻取右侧像素，双线性取两者的平均
+        assert_eq!(sample(&nearest, 0.5, v), Vector3::new(2.0, 0.0, 0.0));
+        assert!((sample(&bilinear, 0.5, v) - Vector3::new(1.5, 0.0, 0.0)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn v_is_flipped() {
+        let texture = texture(WrapMode::Clamp, FilterMode::Nearest);
+        // v接近1是图像的第0行（最上面），v接近0是最下面一行
+        assert_eq!(sample(&texture, 0.1, 0.9).y, 0.0);
+        assert_eq!(sample(&texture, 0.1, 0.1).y, 1.0);
+    }
+
+    #[test]
+    fn srgb_pixels_are_decoded_to_linear() {
+        let path = std::env::temp_dir().join(format!(
+            "restart_raytrace_texture_{}.png",
+            std::process::id()
+        ));
+        image::RgbImage::from_pixel(1, 1, image::Rgb([188, 188, 188]))
+            .save(&path)
+            .unwrap();
+        let open = |srgb| {
+            ImageTexture::open_with(&path, WrapMode::Repeat, FilterMode::Nearest, srgb).unwrap()
+        };
+        let (srgb, raw) = (open(true), open(false));
+        std::fs::remove_file(&path).unwrap();
+        // sRGB编码的188约为线性的0.5
+        assert!((sample(&srgb, 0.5, 0.5).x - 0.5).abs() < 0.01);
+        assert!((sample(&raw, 0.5, 0.5).x - 188.0 / 255.0).abs() < 1e-6);
+    }
+}
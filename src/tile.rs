@@ -0,0 +1,157 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// 图像中的一块，光栅坐标，y向下
+#[derive(Clone, Debug)]
+pub struct Tile {
+    pub xs: Range<usize>,
+    pub ys: Range<usize>,
+}
+
+impl Tile {
+    /// 块中的像素数
+    pub fn pixel_count(&self) -> usize {
+        self.xs.len() * self.ys.len()
+    }
+}
+
+/// 块的渲染顺序
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileOrder {
+    /// 从上到下、从左到右
+    Scanline,
+    /// 从图像中心向外螺旋，先看到画面的主体
+    Spiral,
+}
+
+impl fmt::Display for TileOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Spiral => "spiral",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scanline" => Ok(TileOrder::Scanline),
+            "spiral" => Ok(TileOrder::Spiral),
+            _ => Err(format!(
+                "unknown tile order '{}' (expected scanline or spiral)",
+                s
+            )),
+        }
+    }
+}
+
+/// 把width × height的图像切成边长为tile_size的块，按order排列
+/// 最右一列与最下一行的块可能更小
+pub fn tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+    let positions = match order {
+        TileOrder::Scanline => (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+    };
+    positions
+        .into_iter()
+        .map(|(column, row)| Tile {
+            xs: column * tile_size..((column + 1) * tile_size).min(width),
+            ys: row * tile_size..((row + 1) * tile_size).min(height),
+        })
+        .collect()
+}
+
+/// 从中心开始按右、下、左、上的方向螺旋前进，步长依次为1, 1, 2, 2, 3, 3...
+/// 跳过网格外的位置，直到走遍columns × rows个位置
+fn spiral(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let total = columns * rows;
+    let mut positions = Vec::with_capacity(total);
+    let (mut x, mut y) = ((columns as i64 - 1) / 2, (rows as i64 - 1) / 2);
+    let mut step = 1;
+    let mut direction = 0;
+    while positions.len() < total {
+        for _ in 0..2 {
+            let (dx, dy) = DIRECTIONS[direction];
+            for _ in 0..step {
+                if (0..columns as i64).contains(&x) && (0..rows as i64).contains(&y) {
+                    positions.push((x as usize, y as usize));
+                }
+                x += dx;
+                y += dy;
+            }
+            direction = (direction + 1) % 4;
+        }
+        step += 1;
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个像素被覆盖的次数
+    fn coverage(width: usize, height: usize, tiles: &[Tile]) -> Vec<usize> {
+        let mut counts = vec![0; width * height];
+        for tile in tiles {
+            for y in tile.ys.clone() {
+                for x in tile.xs.clone() {
+                    counts[y * width + x] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn both_orders_cover_every_pixel_once() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral] {
+            for (width, height, tile_size) in
+                [(7, 5, 3), (5, 7, 3), (1, 9, 4), (9, 1, 2), (4, 4, 8)]
+            {
+                let tiles = tiles(width, height, tile_size, order);
+                assert_eq!(
+                    tiles.len(),
+                    width.div_ceil(tile_size) * height.div_ceil(tile_size)
+                );
+                assert!(tiles.iter().all(|tile| tile.pixel_count() > 0));
+                let counts = coverage(width, height, &tiles);
+                assert!(
+                    counts.iter().all(|&count| count == 1),
+                    "{} {}x{} tile {}: {:?}",
+                    order,
+                    width,
+                    height,
+                    tile_size,
+                    counts
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn spiral_starts_at_the_centre_tile() {
+        // 3 × 2个块，中心取偏左上的(1, 0)
+        let first = &tiles(7, 5, 3, TileOrder::Spiral)[0];
+        assert_eq!((first.xs.clone(), first.ys.clone()), (3..6, 0..3));
+        let first = &tiles(9, 9, 3, TileOrder::Spiral)[0];
+        assert_eq!((first.xs.clone(), first.ys.clone()), (3..6, 3..6));
+    }
+
+    #[test]
+    fn spiral_handles_single_row_and_column_grids() {
+        assert_eq!(spiral(1, 1), vec![(0, 0)]);
+        assert_eq!(spiral(1, 4), vec![(0, 1), (0, 2), (0, 0), (0, 3)]);
+        assert_eq!(spiral(5, 1), vec![(2, 0), (3, 0), (1, 0), (4, 0), (0, 0)]);
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+use std::str::FromStr;
+
+use nalgebra::Vector3;
+
+use crate::framebuffer::Image;
+
+/// 色调映射算子，把[0, ∞)的线性颜色压缩到[0, 1]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// 直接截断到1
+    Clamp,
+    /// L / (1 + L)，作用于亮度，保持色相
+    Reinhard,
+    /// 亮度达到白点时映射为1的Reinhard
+    ExtendedReinhard,
+    /// Narkowicz对ACES电影曲线的拟合
+    Aces,
+    /// Hable的Uncharted 2电影曲线
+    Hable,
+}
+
+impl fmt::Display for ToneMapOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ToneMapOperator::Clamp => "clamp",
+            ToneMapOperator::Reinhard => "reinhard",
+            ToneMapOperator::ExtendedReinhard => "extended-reinhard",
+            ToneMapOperator::Aces => "aces",
+            ToneMapOperator::Hable => "hable",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ToneMapOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(ToneMapOperator::Clamp),
+            "reinhard" => Ok(ToneMapOperator::Reinhard),
+            "extended-reinhard" => Ok(ToneMapOperator::ExtendedReinhard),
+            "aces" => Ok(ToneMapOperator::Aces),
+            "hable" => Ok(ToneMapOperator::Hable),
+            _ => Err(format!(
+                "unknown tone mapping operator '{}' (expected clamp, reinhard, extended-reinhard, aces or hable)",
+                s
+            )),
+        }
+    }
+}
+
+/// Rec. 709亮度
+fn luminance(c: Vector3<f64>) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+/// 把颜色缩放到亮度为new_luminance
+fn with_luminance(c: Vector3<f64>, old_luminance: f64, new_luminance: f64) -> Vector3<f64> {
+    if old_luminance > 0.0 {
+        c * (new_luminance / old_luminance)
+    } else {
+        c
+    }
+}
+
+fn aces(x: f64) -> f64 {
+    const A: f64 = 2.51;
+    const B: f64 = 0.03;
+    const C: f64 = 2.43;
+    const D: f64 = 0.59;
+    const E: f64 = 0.14;
+    (x * (A * x + B)) / (x * (C * x + D) + E)
+}
+
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+/// Hable曲线的曝光偏移与线性白点
+const HABLE_EXPOSURE_BIAS: f64 = 2.0;
+const HABLE_WHITE: f64 = 11.2;
+
+/// sRGB的分段OETF，输入线性值，输出编码值
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 后处理：曝光补偿、色调映射、sRGB编码
+/// exposure：曝光补偿，单位为档（EV），颜色乘以2^exposure
+/// white_point：ExtendedReinhard映射为1的亮度，缺省为图像的最大亮度
+#[derive(Clone, Debug)]
+pub struct ToneMapping {
+    pub exposure: f64,
+    pub operator: ToneMapOperator,
+    pub white_point: Option<f64>,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+            white_point: None,
+        }
+    }
+}
+
+/// 不合法的后处理参数
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneMapError {
+    /// 曝光补偿不是有限值
+    InvalidExposure { exposure: f64 },
+    /// 白点不是正的有限值，Extended Reinhard会除以0或得到NaN
+    InvalidWhitePoint { white_point: f64 },
+}
+
+impl fmt::Display for ToneMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToneMapError::InvalidExposure { exposure } => {
+                write!(f, "exposure must be finite, got {}", exposure)
+            }
+            ToneMapError::InvalidWhitePoint { white_point } => {
+                write!(f, "white point must be > 0, got {}", white_point)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ToneMapError {}
+
+impl ToneMapping {
+    /// 检查参数，to_rgb8会先调用它
+    pub fn validate(&self) -> Result<(), ToneMapError> {
+        if !self.exposure.is_finite() {
+            return Err(ToneMapError::InvalidExposure {
+                exposure: self.exposure,
+            });
+        }
+        if let Some(white_point) = self.white_point {
+            if !(white_point > 0.0 && white_point.is_finite()) {
+                return Err(ToneMapError::InvalidWhitePoint { white_point });
+            }
+        }
+        Ok(())
+    }
+
+    /// 对一个已乘以曝光的线性颜色做色调映射，输出[0, 1]内的线性值
+    fn map(&self, c: Vector3<f64>, white_point: f64) -> Vector3<f64> {
+        let mapped = match self.operator {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => {
+                let l = luminance(c);
+                with_luminance(c, l, l / (1.0 + l))
+            }
+            ToneMapOperator::ExtendedReinhard => {
+                let l = luminance(c);
+                let white2 = white_point * white_point;
+                with_luminance(c, l, l * (1.0 + l / white2) / (1.0 + l))
+            }
+            ToneMapOperator::Aces => c.map(aces),
+            ToneMapOperator::Hable => {
+                c.map(|x| hable_partial(x * HABLE_EXPOSURE_BIAS) / hable_partial(HABLE_WHITE))
+            }
+        };
+        mapped.map(|x| x.clamp(0.0, 1.0))
+    }
+
+    /// 转换为sRGB编码的8位RGB，从上到下、从左到右；参数不合法时返回错误
+    pub fn to_rgb8(&self, image: &Image) -> Result<Vec<u8>, ToneMapError> {
+        self.validate()?;
+        let scale = self.exposure.exp2();
+        // 负值与NaN没有物理意义，当作0
+        let pixel = |p: &[f32; 3]| {
+            Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64).map(|c| {
+                if c > 0.0 {
+                    c * scale
+                } else {
+                    0.0
+                }
+            })
+        };
+        let white_point = self.white_point.unwrap_or_else(|| {
+            image
+                .pixels()
+                .iter()
+                .map(|p| luminance(pixel(p)))
+                .fold(0.0, f64::max)
+                .max(1.0)
+        });
+        Ok(image
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = self.map(pixel(p), white_point);
+                [c.x, c.y, c.z].map(|x| (linear_to_srgb(x) * 255.0).round() as u8)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operator(operator: ToneMapOperator) -> ToneMapping {
+        ToneMapping {
+            operator,
+            ..ToneMapping::default()
+        }
+    }
+
+    fn grey(x: f64) -> Vector3<f64> {
+        Vector3::new(x, x, x)
+    }
+
+    #[test]
+    fn operators_at_black_and_mid_grey() {
+        // 数值按各曲线的定义单独算出
+        for (op, expected) in [
+            (ToneMapOperator::Clamp, 0.18),
+            (ToneMapOperator::Reinhard, 0.152_542_372_881_355_94),
+            (ToneMapOperator::ExtendedReinhard, 0.154_258_474_576_271_18),
+            (ToneMapOperator::Aces, 0.266_898_920_389_496_8),
+            (ToneMapOperator::Hable, 0.128_338_448_855_627_62),
+        ] {
+            let mapping = operator(op);
+            assert_eq!(mapping.map(grey(0.0), 4.0), grey(0.0), "{}", op);
+            let mapped = mapping.map(grey(0.18), 4.0);
+            assert!(
+                (mapped - grey(expected)).norm() < 1e-12,
+                "{}: {}",
+                op,
+                mapped
+            );
+        }
+    }
+
+    #[test]
+    fn extended_reinhard_maps_the_white_point_to_one() {
+        let mapping = operator(ToneMapOperator::ExtendedReinhard);
+        for white_point in [1.0, 4.0, 100.0] {
+            let mapped = mapping.map(grey(white_point), white_point);
+            assert!((mapped - grey(1.0)).norm() < 1e-12, "{}", mapped);
+        }
+    }
+
+    #[test]
+    fn srgb_curve_is_continuous_at_the_breakpoint() {
+        const BREAK: f64 = 0.0031308;
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-12);
+        // 线性段与幂函数段在断点两侧都约等于0.04045
+        assert_eq!(linear_to_srgb(BREAK), 12.92 * BREAK);
+        assert!((linear_to_srgb(BREAK) - 0.04045).abs() < 1e-6);
+        let above = BREAK + 1e-12;
+        assert_eq!(linear_to_srgb(above), 1.055 * above.powf(1.0 / 2.4) - 0.055);
+        assert!((linear_to_srgb(above) - linear_to_srgb(BREAK)).abs() < 1e-7);
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let image = Image::from_pixels(1, 1, vec![[0.5, 0.5, 0.5]]);
+        for white_point in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mapping = ToneMapping {
+                operator: ToneMapOperator::ExtendedReinhard,
+                white_point: Some(white_point),
+                ..ToneMapping::default()
+            };
+            assert!(matches!(
+                mapping.to_rgb8(&image),
+                Err(ToneMapError::InvalidWhitePoint { .. })
+            ));
+        }
+        for exposure in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mapping = ToneMapping {
+                exposure,
+                ..ToneMapping::default()
+            };
+            assert!(matches!(
+                mapping.to_rgb8(&image),
+                Err(ToneMapError::InvalidExposure { .. })
+            ));
+        }
+        assert!(ToneMapping::default().to_rgb8(&image).is_ok());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::aabb::AABB;
+
+use super::hitable::{HitRecord, Hitable};
+use super::ray::Ray;
+use nalgebra::{Matrix3, Matrix4, Rotation3, Vector3};
+
+/// 平移
+/// offset：物体整体的偏移量
+pub struct Translate<T: Hitable> {
+    hitable: T,
+    offset: Vector3<f64>,
+}
+
+impl<T: Hitable> Translate<T> {
+    /// 把hitable整体移动offset
+    pub fn new(hitable: T, offset: Vector3<f64>) -> Translate<T> {
+        Translate { hitable, offset }
+    }
+}
+
+impl<T: Hitable> Hitable for Translate<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // 反向移动光线，等价于移动物体
+        let moved_r = r.moved(r.origin() - self.offset, r.direction());
+        self.hitable.hit(&moved_r, t_min, t_max).map(|rec| {
+            let point = rec.point() + self.offset;
+            let normal = rec.normal();
+            rec.moved(point, normal)
+        })
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.hitable
+            .bounding_box(time0, time1)
+            .map(|bbox| AABB::new(bbox.min() + self.offset, bbox.max() + self.offset))
+    }
+}
+
+/// 一般仿射变换
+/// matrix：物体空间到世界空间的4x4齐次矩阵
+/// inverse：世界空间到物体空间
+/// normal_matrix：法线变换矩阵，即线性部分逆矩阵的转置
+pub struct Transform<T: Hitable> {
+    hitable: T,
+    matrix: Matrix4<f64>,
+    inverse: Matrix4<f64>,
+    normal_matrix: Matrix3<f64>,
+}
+
+impl<T: Hitable> Transform<T> {
+    /// matrix不可逆或逆矩阵含有非有限值时返回None
+    /// 例如各轴缩放都极小时，行列式会下溢为0
+    pub fn new(hitable: T, matrix: Matrix4<f64>) -> Option<Transform<T>> {
+        let inverse = matrix.try_inverse()?;
+        if !matrix.iter().chain(inverse.iter()).all(|x| x.is_finite()) {
+            return None;
+        }
+        Some(Transform::with_inverse(hitable, matrix, inverse))
+    }
+
+    /// 已知逆矩阵时直接构造，如旋转矩阵的逆即其转置
+    fn with_inverse(hitable: T, matrix: Matrix4<f64>, inverse: Matrix4<f64>) -> Transform<T> {
+        let normal_matrix = inverse.fixed_view::<3, 3>(0, 0).transpose();
+        Transform {
+            hitable,
+            matrix,
+            inverse,
+            normal_matrix,
+        }
+    }
+
+    fn transform_point(m: &Matrix4<f64>, p: &Vector3<f64>) -> Vector3<f64> {
+        (m * p.push(1.0)).xyz()
+    }
+
+    fn transform_vector(m: &Matrix4<f64>, v: &Vector3<f64>) -> Vector3<f64> {
+        (m * v.push(0.0)).xyz()
+    }
+}
+
+impl<T: Hitable> Hitable for Transform<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // 把光线变换到物体空间，方向不归一化，t在两个空间中相同
+        let object_r = r.moved(
+            Self::transform_point(&self.inverse, &r.origin()),
+            Self::transform_vector(&self.inverse, &r.direction()),
+        );
+        self.hitable.hit(&object_r, t_min, t_max).map(|rec| {
+            let point = Self::transform_point(&self.matrix, &rec.point());
+            let normal = (self.normal_matrix * rec.normal()).normalize();
+            rec.moved(point, normal)
+        })
+    }
+    /// 变换物体包围盒的八个角点，再求新的轴对齐包围盒
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        let bbox = self.hitable.bounding_box(time0, time1)?;
+        let mut min = Vector3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Vector3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for i in 0..8 {
+            // i的三个二进制位分别选择x、y、z上的min或max
+            let corner = Vector3::from_fn(|axis, _| {
+                if i & (1 << axis) == 0 {
+                    bbox.min()[axis]
+                } else {
+                    bbox.max()[axis]
+                }
+            });
+            let p = Self::transform_point(&self.matrix, &corner);
+            min = min.inf(&p);
+            max = max.sup(&p);
+        }
+        Some(AABB::new(min, max))
+    }
+}
+
+/// 绕坐标轴旋转degrees度的变换，旋转总是可逆的
+fn rotation<T: Hitable>(hitable: T, axis: Vector3<f64>, degrees: f64) -> Transform<T> {
+    let rotation =
+        Rotation3::from_axis_angle(&nalgebra::Unit::new_normalize(axis), degrees.to_radians());
+    Transform::with_inverse(
+        hitable,
+        rotation.to_homogeneous(),
+        rotation.inverse().to_homogeneous(),
+    )
+}
+
+/// 绕x轴旋转
+pub struct RotateX<T: Hitable>(Transform<T>);
+
+impl<T: Hitable> RotateX<T> {
+    /// 绕过原点的x轴旋转degrees度，右手定则
+    pub fn new(hitable: T, degrees: f64) -> RotateX<T> {
+        RotateX(rotation(hitable, Vector3::x(), degrees))
+    }
+}
+
+impl<T: Hitable> Hitable for RotateX<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.0.hit(r, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.0.bounding_box(time0, time1)
+    }
+}
+
+/// 绕y轴旋转
+pub struct RotateY<T: Hitable>(Transform<T>);
+
+impl<T: Hitable> RotateY<T> {
+    /// 绕过原点的y轴旋转degrees度，右手定则
+    pub fn new(hitable: T, degrees: f64) -> RotateY<T> {
+        RotateY(rotation(hitable, Vector3::y(), degrees))
+    }
+}
+
+impl<T: Hitable> Hitable for RotateY<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.0.hit(r, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.0.bounding_box(time0, time1)
+    }
+}
+
+/// 绕z轴旋转
+pub struct RotateZ<T: Hitable>(Transform<T>);
+
+impl<T: Hitable> RotateZ<T> {
+    /// 绕过原点的z轴旋转degrees度，右手定则
+    pub fn new(hitable: T, degrees: f64) -> RotateZ<T> {
+        RotateZ(rotation(hitable, Vector3::z(), degrees))
+    }
+}
+
+impl<T: Hitable> Hitable for RotateZ<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.0.hit(r, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.0.bounding_box(time0, time1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+
+    fn unit_sphere() -> Sphere {
+        Sphere::new(
+            Vector3::zeros(),
+            1.0,
+            Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    /// 先沿x拉长两倍，再绕z轴转90度：得到x² + y²/4 + z² = 1的椭球
+    fn ellipsoid() -> Transform<Sphere> {
+        let matrix = rotation_matrix(Vector3::z(), 90.0)
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 1.0, 1.0));
+        Transform::new(unit_sphere(), matrix).unwrap()
+    }
+
+    fn rotation_matrix(axis: Vector3<f64>, degrees: f64) -> Matrix4<f64> {
+        Rotation3::from_axis_angle(&nalgebra::Unit::new_normalize(axis), degrees.to_radians())
+            .to_homogeneous()
+    }
+
+    #[test]
+    fn scaled_and_rotated_hit_matches_analytic_ellipsoid() {
+        let ray = Ray::new(
+            Vector3::new(-0.5, 3.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            0.0,
+            0.5,
+        );
+        let rec = ellipsoid().hit(&ray, 0.001, f64::MAX).unwrap();
+        let point = Vector3::new(-0.5, 3f64.sqrt(), 0.0);
+        assert!((rec.point() - point).norm() < 1e-9, "{}", rec.point());
+        // 隐式曲面的梯度(x, y/4, z)，直接用matrix变换法线会得到错误的方向
+        let normal = Vector3::new(point.x, point.y / 4.0, point.z).normalize();
+        assert!((rec.normal() - normal).norm() < 1e-9, "{}", rec.normal());
+    }
+
+    #[test]
+    fn rotation_moves_hit_point_and_normal() {
+        let sphere = Sphere::new(
+            Vector3::new(1.0, 0.0, 0.0),
+            0.5,
+            Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5))),
+        );
+        // 绕z轴转90度后球心在(0, 1, 0)
+        let rotated = RotateZ::new(sphere, 90.0);
+        let ray = Ray::new(
+            Vector3::new(0.0, 5.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            0.0,
+            0.5,
+        );
+        let rec = rotated.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!((rec.point() - Vector3::new(0.0, 1.5, 0.0)).norm() < 1e-9);
+        assert!((rec.normal() - Vector3::y()).norm() < 1e-9);
+        assert!((rec.time() - 3.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bounding_box_contains_transformed_surface() {
+        let matrix = Matrix4::new_translation(&Vector3::new(1.0, -2.0, 0.5))
+            * rotation_matrix(Vector3::new(1.0, 1.0, 0.0), 30.0)
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 0.5, 1.0));
+        let transformed = Transform::new(unit_sphere(), matrix).unwrap();
+        let bbox = transformed.bounding_box(0.0, 1.0).unwrap();
+        for i in 0..32 {
+            for j in 0..=16 {
+                let phi = i as f64 / 32.0 * std::f64::consts::TAU;
+                let theta = j as f64 / 16.0 * std::f64::consts::PI;
+                let p = Vector3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                let world = (matrix * p.push(1.0)).xyz();
+                for axis in 0..3 {
+                    assert!(bbox.min()[axis] - 1e-9 <= world[axis]);
+                    assert!(world[axis] <= bbox.max()[axis] + 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn singular_matrix_is_rejected() {
+        let tiny = Matrix4::new_scaling(1e-110);
+        assert!(Transform::new(unit_sphere(), tiny).is_none());
+        let flat = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 0.0, 1.0));
+        assert!(Transform::new(unit_sphere(), flat).is_none());
+        let nan = Matrix4::new_scaling(f64::NAN);
+        assert!(Transform::new(unit_sphere(), nan).is_none());
+    }
+}
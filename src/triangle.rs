@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+
+use super::hitable::{HitRecord, Hitable};
+use super::material::Material;
+use super::ray::Ray;
+use nalgebra::Vector3;
+
+/// 包围盒的最小厚度，防止轴对齐三角形的包围盒退化为平面
+const PAD: f64 = 0.0001;
+
+/// 判定为退化（面积为0或光线与平面平行）的行列式阈值
+const DET_EPSILON: f64 = 1e-12;
+
+/// Möller–Trumbore求交
+/// 输入三个顶点与光线
+/// 输出：光线参数t，以及p1、p2的重心坐标b1、b2
+pub fn intersect(
+    p: &[Vector3<f64>; 3],
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let e1 = p[1] - p[0];
+    let e2 = p[2] - p[0];
+    let pvec = r.direction().cross(&e2);
+    let det = e1.dot(&pvec);
+    // 退化三角形的det恒为0，直接返回，避免除零产生NaN
+    if det.is_nan() || det.abs() < DET_EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin() - p[0];
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&e1);
+    let b2 = r.direction().dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = e2.dot(&qvec) * inv_det;
+    if t > t_min && t < t_max {
+        Some((t, b1, b2))
+    } else {
+        None
+    }
+}
+
+/// 三个顶点的包围盒，在退化的轴上加厚
+pub fn triangle_bounding_box(p: &[Vector3<f64>; 3]) -> AABB {
+    let pad = Vector3::new(PAD, PAD, PAD);
+    let min = p[0].inf(&p[1]).inf(&p[2]);
+    let max = p[0].sup(&p[1]).sup(&p[2]);
+    AABB::new(min - pad, max + pad)
+}
+
+/// 按重心坐标插值法线，插值结果不可用时退回几何法线
+pub fn shading_normal(
+    normals: &[Vector3<f64>; 3],
+    b1: f64,
+    b2: f64,
+    geometric: Vector3<f64>,
+) -> Vector3<f64> {
+    let n = (1.0 - b1 - b2) * normals[0] + b1 * normals[1] + b2 * normals[2];
+    n.try_normalize(DET_EPSILON).unwrap_or(geometric)
+}
+
+/// 按重心坐标插值uv
+pub fn interpolate_uv(uvs: &[(f64, f64); 3], b1: f64, b2: f64) -> (f64, f64) {
+    let b0 = 1.0 - b1 - b2;
+    (
+        b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0,
+        b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1,
+    )
+}
+
+/// 三角形
+/// vertices：逆时针顺序的三个顶点，几何法线朝向逆时针一侧
+/// normals：可选的顶点法线，用于平滑着色
+/// uvs：顶点uv坐标，缺省时uv即为重心坐标
+pub struct Triangle {
+    vertices: [Vector3<f64>; 3],
+    normals: Option<[Vector3<f64>; 3]>,
+    uvs: [(f64, f64); 3],
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    /// 不带顶点属性的平面三角形，p0、p1、p2为逆时针顺序
+    pub fn new(
+        p0: Vector3<f64>,
+        p1: Vector3<f64>,
+        p2: Vector3<f64>,
+        material: impl Material + 'static,
+    ) -> Triangle {
+        Triangle::with_material(p0, p1, p2, Arc::new(material))
+    }
+
+    /// 平面三角形，材质由多个物体共享
+    pub fn with_material(
+        p0: Vector3<f64>,
+        p1: Vector3<f64>,
+        p2: Vector3<f64>,
+        material: Arc<dyn Material>,
+    ) -> Triangle {
+        Triangle::with_attributes([p0, p1, p2], None, None, material)
+    }
+
+    /// 带顶点法线与uv的三角形，材质可与其他三角形共享
+    pub fn with_attributes(
+        vertices: [Vector3<f64>; 3],
+        normals: Option<[Vector3<f64>; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        material: Arc<dyn Material>,
+    ) -> Triangle {
+        Triangle {
+            vertices,
+            normals,
+            uvs: uvs.unwrap_or([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+            material,
+        }
+    }
+}
+
+impl Hitable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect(&self.vertices, r, t_min, t_max)?;
+        let geometric = (self.vertices[1] - self.vertices[0])
+            .cross(&(self.vertices[2] - self.vertices[0]))
+            .normalize();
+        let normal = match &self.normals {
+            Some(normals) => shading_normal(normals, b1, b2, geometric),
+            None => geometric,
+        };
+        let (u, v) = interpolate_uv(&self.uvs, b1, b2);
+        Some(HitRecord::new(
+            r.at(t),
+            normal,
+            t,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+    /// 返回三角形的包围盒
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(triangle_bounding_box(&self.vertices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    fn gray() -> Lambertian {
+        Lambertian::new(SolidColor::new(Vector3::new(0.5, 0.5, 0.5)))
+    }
+
+    /// xy平面上的直角三角形，法线朝+z
+    fn unit_triangle() -> Triangle {
+        Triangle::new(Vector3::zeros(), Vector3::x(), Vector3::y(), gray())
+    }
+
+    /// 从z=1沿-z射向(x, y, 0)的光线
+    fn down_at(x: f64, y: f64) -> Ray {
+        Ray::new(
+            Vector3::new(x, y, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            0.0,
+            0.5,
+        )
+    }
+
+    #[test]
+    fn degenerate_triangle_and_parallel_ray_miss() {
+        let collinear = Triangle::new(
+            Vector3::zeros(),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(2.0, 2.0, 0.0),
+            gray(),
+        );
+        assert!(collinear.hit(&down_at(1.0, 1.0), 0.001, f64::MAX).is_none());
+
+        let parallel = Ray::new(Vector3::new(-1.0, 0.25, 0.0), Vector3::x(), 0.0, 0.5);
+        assert!(unit_triangle().hit(&parallel, 0.001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn hits_stop_at_the_edges() {
+        let triangle = unit_triangle();
+        // 斜边x + y = 1的两侧
+        assert!(triangle
+            .hit(&down_at(0.5, 0.499), 0.001, f64::MAX)
+            .is_some());
+        assert!(triangle
+            .hit(&down_at(0.5, 0.501), 0.001, f64::MAX)
+            .is_none());
+        // 直角边y = 0的两侧
+        assert!(triangle.hit(&down_at(0.5, 1e-6), 0.001, f64::MAX).is_some());
+        assert!(triangle
+            .hit(&down_at(0.5, -1e-6), 0.001, f64::MAX)
+            .is_none());
+    }
+
+    #[test]
+    fn uv_and_normal_follow_barycentrics() {
+        let triangle = Triangle::with_attributes(
+            [Vector3::zeros(), Vector3::x(), Vector3::y()],
+            Some([Vector3::z(), Vector3::z(), Vector3::y()]),
+            Some([(1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]),
+            Arc::new(gray()),
+        );
+        // 重心坐标(0.25, 0.25, 0.5)
+        let rec = triangle.hit(&down_at(0.25, 0.5), 0.001, f64::MAX).unwrap();
+        assert!((rec.time() - 1.0).abs() < 1e-12);
+        assert!((rec.u() - 0.5).abs() < 1e-12 && (rec.v() - 0.75).abs() < 1e-12);
+        let normal = Vector3::new(0.0, 1.0, 1.0).normalize();
+        assert!((rec.normal() - normal).norm() < 1e-12, "{}", rec.normal());
+
+        // 没有顶点法线时使用逆时针一侧的几何法线，uv即重心坐标
+        let rec = unit_triangle()
+            .hit(&down_at(0.25, 0.5), 0.001, f64::MAX)
+            .unwrap();
+        assert_eq!(rec.normal(), Vector3::z());
+        assert!((rec.u() - 0.25).abs() < 1e-12 && (rec.v() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn nan_direction_misses() {
+        let ray = Ray::new(
+            Vector3::new(0.25, 0.25, 1.0),
+            Vector3::new(f64::NAN, 0.0, -1.0),
+            0.0,
+            0.5,
+        );
+        assert!(unit_triangle().hit(&ray, 0.001, f64::MAX).is_none());
+    }
+}